@@ -0,0 +1,60 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use tokio::fs;
+use crate::models::{Config, ResponseCache};
+use crate::utils;
+
+lazy_static! {
+    static ref SETTINGS: RwLock<Option<ResponseCache>> = RwLock::new(None);
+}
+
+/// 설정의 `response_cache` 로 디스크 캐시를 켜거나 끕니다.
+pub fn install(config: &Config) {
+    *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = config.response_cache.clone();
+}
+
+fn settings() -> Option<ResponseCache> {
+    SETTINGS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// URL 의 캐시 파일 경로. 파일 이름은 URL 의 해시입니다.
+fn path_for(dir: &str, url: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    PathBuf::from(dir).join(format!("{:016x}.html", hasher.finish()))
+}
+
+/// TTL 안에 저장된 응답이 있으면 돌려줍니다.
+pub async fn read(url: &str) -> Option<String> {
+    let cfg = settings()?;
+    let path = path_for(&cfg.dir, url);
+    let modified = fs::metadata(&path).await.ok()?.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+    if age > Duration::from_secs(cfg.ttl_secs) {
+        return None;
+    }
+    let body = fs::read_to_string(&path).await.ok()?;
+    debug!("response cache hit: {}", url);
+    Some(body)
+}
+
+/// 캐시가 켜져 있으면 응답 본문을 저장합니다. 실패해도 수집은 계속합니다.
+pub async fn write(url: &str, body: &str) {
+    let cfg = match settings() {
+        Some(v) => v,
+        None => return,
+    };
+    let path = path_for(&cfg.dir, url);
+    let result = match fs::create_dir_all(&cfg.dir).await {
+        Ok(()) => utils::write_atomic(&path, body.as_bytes()).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!("Failed to write response cache {}: {}", path.display(), e);
+    }
+}
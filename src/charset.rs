@@ -0,0 +1,134 @@
+use encoding_rs::{EUC_KR, Encoding, UTF_8};
+use log::debug;
+
+// <meta> 선언을 찾을 때 살펴보는 문서 앞부분의 길이
+const META_SCAN_BYTES: usize = 4096;
+
+/// 인코딩 이름으로 인코딩을 찾습니다. WHATWG 표준 이름에 없는 CP949 별칭도 받습니다.
+fn for_label(label: &str) -> Option<&'static Encoding> {
+    let label = label.trim();
+    match label.to_ascii_lowercase().as_str() {
+        // encoding_rs 의 EUC-KR 은 CP949(통합형 한글 코드) 전체를 다룹니다.
+        "cp949" | "ms949" | "uhc" | "x-windows-949" => Some(EUC_KR),
+        _ => Encoding::for_label(label.as_bytes()),
+    }
+}
+
+/// `charset=` 뒤의 인코딩 이름을 읽습니다. 따옴표와 공백은 건너뜁니다.
+fn label_after_charset(text: &str) -> Option<&str> {
+    let start = text.find("charset")? + "charset".len();
+    let rest = text[start..].trim_start().strip_prefix('=')?;
+    let rest = rest.trim_start().trim_start_matches(['"', '\'']);
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))
+        .unwrap_or(rest.len());
+    Some(&rest[..end]).filter(|v| !v.is_empty())
+}
+
+/// Content-Type 헤더의 charset
+fn from_header(content_type: Option<&str>) -> Option<&'static Encoding> {
+    let value = content_type?.to_ascii_lowercase();
+    for_label(label_after_charset(&value)?)
+}
+
+/// 문서 앞부분의 `<meta charset>` / `<meta http-equiv content="...; charset=">` 선언
+fn from_meta(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_SCAN_BYTES)];
+    // 앞부분은 ASCII 로만 비교하므로 나머지 바이트는 뭉개도 됩니다.
+    let head: String = head.iter().map(|b| b.to_ascii_lowercase() as char).collect();
+    let mut rest = head.as_str();
+    while let Some(pos) = rest.find("<meta") {
+        let tag = &rest[pos..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        if let Some(enc) = label_after_charset(tag).and_then(for_label) {
+            // 바이트로 받은 문서에서 UTF-16 선언은 UTF-8 로 취급합니다.
+            return Some(enc.output_encoding());
+        }
+        rest = &rest[pos + "<meta".len()..];
+    }
+    None
+}
+
+/// 응답 본문을 문자열로 바꿉니다.
+///
+/// BOM 이 있으면 항상 BOM 을 따르고, 그다음 사이트 설정의 `encoding`, Content-Type 헤더,
+/// `<meta>` 선언 순으로 고르며, 모두 없으면 UTF-8 입니다.
+pub fn decode(bytes: &[u8], content_type: Option<&str>, encoding: Option<&str>) -> String {
+    let encoding = encoding
+        .and_then(for_label)
+        .or_else(|| from_header(content_type))
+        .or_else(|| from_meta(bytes))
+        .unwrap_or(UTF_8);
+    let (text, used, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        debug!("malformed {} sequences replaced", used.name());
+    }
+    text.into_owned()
+}
+
+/// 설정에 쓴 인코딩 이름이 유효한지 확인합니다.
+pub fn is_known(label: &str) -> bool {
+    for_label(label).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "한글 제목";
+
+    fn euc_kr(text: &str) -> Vec<u8> {
+        EUC_KR.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn euc_kr_declared_only_in_meta() {
+        let html = format!(r#"<html><head><meta charset="euc-kr"><title>{}</title></head></html>"#, TEXT);
+        let text = decode(&euc_kr(&html), Some("text/html"), None);
+        assert!(text.contains(TEXT), "{}", text);
+
+        let html = format!(
+            r#"<HEAD><META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=EUC-KR"></HEAD>{}"#,
+            TEXT
+        );
+        assert!(decode(&euc_kr(&html), None, None).contains(TEXT));
+    }
+
+    #[test]
+    fn header_charset_before_meta() {
+        let html = format!(r#"<meta charset="utf-8">{}"#, TEXT);
+        let text = decode(&euc_kr(&html), Some("text/html; charset=\"EUC-KR\""), None);
+        assert!(text.contains(TEXT));
+    }
+
+    #[test]
+    fn bom_wins_over_everything() {
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice(format!(r#"<meta charset="euc-kr">{}"#, TEXT).as_bytes());
+        let text = decode(&bytes, Some("text/html; charset=euc-kr"), Some("euc-kr"));
+        assert!(text.contains(TEXT));
+        assert!(!text.starts_with('\u{feff}'));
+    }
+
+    #[test]
+    fn override_wins_over_header_and_meta() {
+        let html = format!(r#"<meta charset="utf-8">{}"#, TEXT);
+        let text = decode(&euc_kr(&html), Some("text/html; charset=utf-8"), Some("euc-kr"));
+        assert!(text.contains(TEXT));
+    }
+
+    #[test]
+    fn cp949_alias() {
+        assert!(is_known("cp949"));
+        assert!(is_known(" MS949 "));
+        assert!(!is_known("not-an-encoding"));
+        assert!(decode(&euc_kr(TEXT), None, Some("CP949")).contains(TEXT));
+        let html = format!(r#"<meta charset='cp949'>{}"#, TEXT);
+        assert!(decode(&euc_kr(&html), None, None).contains(TEXT));
+    }
+
+    #[test]
+    fn defaults_to_utf8() {
+        assert_eq!(decode(TEXT.as_bytes(), None, None), TEXT);
+    }
+}
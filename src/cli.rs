@@ -0,0 +1,85 @@
+use anyhow::Result;
+
+pub const DEFAULT_CONFIG_PATH: &str = "./config.json";
+
+pub const USAGE: &str = "\
+Usage: textminer [--config <path>] [command]
+
+Commands:
+  run                      Scrape every site on its schedule (default)
+  once                     Scrape every site once and exit (for cron)
+  check-config             Validate the config file and exit
+  parse-file <host> <html> Parse a saved HTML file and print the posts
+  parse-comments <json>    Parse a saved DC comment response and print the comments
+
+Options:
+  -c, --config <path>      Config file path (default: ./config.json)
+  -h, --help               Print this help";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Once,
+    CheckConfig,
+    ParseFile { host: String, html: String },
+    ParseComments { json: String },
+    Help,
+}
+
+#[derive(Debug)]
+pub struct Cli {
+    pub config: String,
+    pub command: Command,
+}
+
+/// 프로그램 이름을 뺀 인자 목록을 해석합니다.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Cli> {
+    let mut config = DEFAULT_CONFIG_PATH.to_string();
+    let mut positional = vec![];
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--config" => {
+                config = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("{} requires a path", arg))?;
+            }
+            "-h" | "--help" => {
+                return Ok(Cli { config, command: Command::Help });
+            }
+            _ if arg.starts_with("--config=") => {
+                config = arg["--config=".len()..].to_string();
+            }
+            _ if arg.starts_with('-') => anyhow::bail!("unknown option: {}", arg),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        None | Some("run") => Command::Run,
+        Some("once") => Command::Once,
+        Some("check-config") => Command::CheckConfig,
+        Some("parse-file") => {
+            let host = positional
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("parse-file requires <host> <html>"))?;
+            let html = positional
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("parse-file requires <host> <html>"))?;
+            Command::ParseFile { host, html }
+        }
+        Some("parse-comments") => {
+            let json = positional
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("parse-comments requires <json>"))?;
+            Command::ParseComments { json }
+        }
+        Some(other) => anyhow::bail!("unknown command: {}", other),
+    };
+    if let Some(extra) = positional.next() {
+        anyhow::bail!("unexpected argument: {}", extra);
+    }
+    Ok(Cli { config, command })
+}
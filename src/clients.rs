@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use url::Url;
+use crate::config::ConfigIssue;
+use crate::models::{ClientProfile, Config, FetchMode, RetryPolicy};
+
+// HTTP client configuration constants
+const HTTP_TIMEOUT_SECS: u64 = 30;
+const POOL_MAX_IDLE_PER_HOST: usize = 10;
+const MAX_REDIRECTS: usize = 10;
+const APP_USER_AGENT: &str = "RS Simple Scraper/1.0";
+const BOT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:98.0) Gecko/20100101 Firefox/98.0";
+// 봇 클라이언트의 Authorization 헤더 값은 설정 파일이 아닌 환경 변수에서 읽습니다.
+const BOT_AUTHORIZATION_ENV: &str = "TEXTMINER_BOT_AUTHORIZATION";
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const BOT_PROFILE: &str = "bot";
+
+/// 프로필로 만든 클라이언트와 재시도 설정
+#[derive(Debug, Clone, Default)]
+pub struct HttpClient {
+    pub client: reqwest::Client,
+    pub retry: RetryPolicy,
+    /// robots.txt 규칙을 고를 때 쓰는 User-Agent (없으면 빈 문자열)
+    pub user_agent: String,
+}
+
+lazy_static! {
+    static ref CLIENTS: RwLock<HashMap<String, HttpClient>> =
+        RwLock::new(build_all(&Config::default()).unwrap_or_default());
+}
+
+/// 가져오기 방식에 해당하는 내장 프로필 이름
+pub fn profile_for(mode: FetchMode) -> &'static str {
+    match mode {
+        FetchMode::Default => DEFAULT_PROFILE,
+        FetchMode::Bot => BOT_PROFILE,
+    }
+}
+
+/// 설정에 없어도 항상 존재하는 프로필. 같은 이름을 설정하면 덮어씁니다.
+fn builtin_profiles() -> Vec<ClientProfile> {
+    let mut bot_headers = std::collections::BTreeMap::new();
+    if let Ok(v) = std::env::var(BOT_AUTHORIZATION_ENV) {
+        bot_headers.insert("Authorization".to_string(), v);
+    }
+    vec![
+        ClientProfile {
+            name: DEFAULT_PROFILE.to_string(),
+            user_agent: Some(APP_USER_AGENT.to_string()),
+            ..Default::default()
+        },
+        ClientProfile {
+            name: BOT_PROFILE.to_string(),
+            user_agent: Some(BOT_USER_AGENT.to_string()),
+            headers: bot_headers,
+            ..Default::default()
+        },
+    ]
+}
+
+/// 오류와 그 원인들을 한 줄로 이어 붙입니다.
+fn describe(e: &dyn std::error::Error) -> String {
+    let mut parts = vec![e.to_string()];
+    let mut source = e.source();
+    while let Some(cause) = source {
+        let part = cause.to_string();
+        if parts.last() != Some(&part) {
+            parts.push(part);
+        }
+        source = cause.source();
+    }
+    parts.join(": ")
+}
+
+/// 프로필 하나로 클라이언트를 만듭니다. 실패하면 (필드, 이유)를 돌려줍니다.
+///
+/// `seed_urls` 는 쿠키 저장소를 쓸 때 고정 쿠키를 넣어 둘 주소들입니다.
+fn build(profile: &ClientProfile, seed_urls: &[Url]) -> Result<HttpClient, (String, String)> {
+    let mut headers = HeaderMap::new();
+    for (k, v) in &profile.headers {
+        let name = HeaderName::from_bytes(k.as_bytes())
+            .map_err(|e| (format!("headers.{}", k), e.to_string()))?;
+        let value = HeaderValue::from_str(v)
+            .map_err(|e| (format!("headers.{}", k), e.to_string()))?;
+        headers.insert(name, value);
+    }
+
+    let cookie_line = profile
+        .cookies
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(profile.timeout_secs.unwrap_or(HTTP_TIMEOUT_SECS)))
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .redirect(match profile.max_redirects.unwrap_or(MAX_REDIRECTS) {
+            0 => reqwest::redirect::Policy::none(),
+            n => reqwest::redirect::Policy::limited(n),
+        });
+
+    if profile.cookie_store {
+        // 쿠키 저장소가 있으면 Cookie 헤더를 직접 넣지 않고 저장소에 고정 쿠키를 넣어 둡니다.
+        let jar = Arc::new(reqwest::cookie::Jar::default());
+        for url in seed_urls {
+            for (k, v) in &profile.cookies {
+                jar.add_cookie_str(&format!("{}={}; Path=/", k, v), url);
+            }
+        }
+        builder = builder.cookie_provider(jar);
+    } else if !cookie_line.is_empty() {
+        let value = HeaderValue::from_str(&cookie_line)
+            .map_err(|e| ("cookies".to_string(), e.to_string()))?;
+        headers.insert(reqwest::header::COOKIE, value);
+    }
+
+    if let Some(ua) = &profile.user_agent {
+        builder = builder.user_agent(ua);
+    }
+    if let Some(proxy) = &profile.proxy {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| ("proxy".to_string(), describe(&e)))?;
+        builder = builder.proxy(proxy);
+    }
+
+    let client = builder
+        .default_headers(headers)
+        .build()
+        .map_err(|e| ("(client)".to_string(), describe(&e)))?;
+    Ok(HttpClient {
+        client,
+        retry: profile.retry.clone(),
+        user_agent: profile.user_agent.clone().unwrap_or_default(),
+    })
+}
+
+/// 내장 프로필과 설정의 프로필로 모든 클라이언트를 만듭니다.
+pub fn build_all(config: &Config) -> Result<HashMap<String, HttpClient>, Vec<ConfigIssue>> {
+    let mut clients = HashMap::new();
+    let mut issues = vec![];
+
+    for profile in builtin_profiles() {
+        if let Ok(client) = build(&profile, &[]) {
+            clients.insert(profile.name, client);
+        }
+    }
+
+    for (i, profile) in config.profiles.iter().enumerate() {
+        let seed_urls: Vec<Url> = config
+            .sites
+            .iter()
+            .filter(|s| s.profile.as_deref() == Some(profile.name.as_str()))
+            .filter_map(|s| Url::parse(&s.url).ok())
+            .collect();
+        match build(profile, &seed_urls) {
+            Ok(client) => {
+                clients.insert(profile.name.clone(), client);
+            }
+            Err((field, reason)) => issues.push(ConfigIssue {
+                field: format!("profiles[{}].{}", i, field),
+                reason,
+            }),
+        }
+    }
+
+    if issues.is_empty() { Ok(clients) } else { Err(issues) }
+}
+
+/// 설정의 프로필로 전역 클라이언트 목록을 교체합니다.
+pub fn install(config: &Config) -> anyhow::Result<()> {
+    let clients = build_all(config).map_err(|issues| {
+        let reasons: Vec<String> = issues.iter().map(|i| format!("{}: {}", i.field, i.reason)).collect();
+        anyhow::anyhow!("failed to build HTTP clients: {}", reasons.join("; "))
+    })?;
+    *CLIENTS.write().unwrap_or_else(|e| e.into_inner()) = clients;
+    Ok(())
+}
+
+/// 이름으로 클라이언트를 찾습니다. 없으면 기본 프로필을 사용합니다.
+pub fn get(name: &str) -> HttpClient {
+    let clients = CLIENTS.read().unwrap_or_else(|e| e.into_inner());
+    clients
+        .get(name)
+        .or_else(|| clients.get(DEFAULT_PROFILE))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 내장 프로필 이름과 설정된 프로필 이름
+pub fn profile_names(config: &Config) -> Vec<String> {
+    let mut names: Vec<String> = builtin_profiles().into_iter().map(|p| p.name).collect();
+    names.extend(config.profiles.iter().map(|p| p.name.clone()));
+    names.sort_unstable();
+    names.dedup();
+    names
+}
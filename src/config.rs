@@ -0,0 +1,554 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use serde::Serialize;
+use url::Url;
+use anyhow::Result;
+use log::{error, info};
+use tokio::fs;
+use crate::models::{Config, Site};
+use crate::scheduler::Schedule;
+use crate::posttime;
+use crate::scrapers::pagination;
+use crate::{charset, clients, feed, scrapers};
+
+const UNSUPPORTED_FORMAT: &str = "unsupported extension, expected .json, .toml, .yaml or .yml";
+
+/// 설정 파일의 한 항목에 대한 문제
+#[derive(Debug)]
+pub struct ConfigIssue {
+    /// 문제가 된 필드 경로 (예: `sites[2].url`)
+    pub field: String,
+    pub reason: String,
+}
+
+/// 설정 파일을 읽거나 검증하지 못했을 때의 오류
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: String,
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigError {
+    fn single(path: &str, field: &str, reason: impl ToString) -> Self {
+        ConfigError {
+            path: path.to_string(),
+            issues: vec![ConfigIssue {
+                field: field.to_string(),
+                reason: reason.to_string(),
+            }],
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid config {}", self.path)?;
+        for issue in &self.issues {
+            write!(f, "\n  - {}: {}", issue.field, issue.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// 설정 파일을 읽고 검증합니다. 파일이 없거나 형식/값이 잘못되면 오류를 돌려줍니다.
+pub async fn load(path: &str) -> Result<Config> {
+    if Format::from_path(path).is_none() {
+        Err(ConfigError::single(path, "(file)", UNSUPPORTED_FORMAT))?;
+    }
+    let content = fs::read_to_string(path)
+        .await
+        .map_err(|e| ConfigError::single(path, "(file)", e))?;
+    let config = parse(path, &content)?;
+    validate(path, &config)?;
+    Ok(config)
+}
+
+/// 설정 파일 형식. 파일 확장자로 고릅니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// 설정 문자열을 해석합니다. 오류 메시지에는 문제가 된 필드 경로가 포함됩니다.
+///
+/// 모든 문자열 값의 `${VAR}` / `${VAR:-기본값}` 은 환경 변수로 치환됩니다.
+pub fn parse(path: &str, content: &str) -> Result<Config, ConfigError> {
+    let format = Format::from_path(path).ok_or_else(|| {
+        ConfigError::single(path, "(file)", UNSUPPORTED_FORMAT)
+    })?;
+    let syntax = |e: &dyn fmt::Display| ConfigError::single(path, "(root)", e.to_string().trim_end());
+    let mut value: serde_json::Value = match format {
+        Format::Json => serde_json::from_str(content).map_err(|e| syntax(&e))?,
+        Format::Toml => toml::from_str(content).map_err(|e| syntax(&e))?,
+        Format::Yaml => serde_yaml::from_str(content).map_err(|e| syntax(&e))?,
+    };
+
+    let mut issues = vec![];
+    interpolate(&mut value, "", &mut issues);
+    if !issues.is_empty() {
+        return Err(ConfigError {
+            path: path.to_string(),
+            issues,
+        });
+    }
+
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let field = match e.path().to_string() {
+            p if p == "." => "(root)".to_string(),
+            p => p,
+        };
+        ConfigError::single(path, &field, e.inner())
+    })
+}
+
+/// 값 트리의 모든 문자열에 환경 변수 치환을 적용합니다.
+fn interpolate(value: &mut serde_json::Value, field: &str, issues: &mut Vec<ConfigIssue>) {
+    match value {
+        serde_json::Value::String(s) => match expand_env(s) {
+            Ok(v) => *s = v,
+            Err(reason) => issues.push(ConfigIssue {
+                field: field.to_string(),
+                reason,
+            }),
+        },
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                interpolate(item, &format!("{}[{}]", field, i), issues);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                let child = if field.is_empty() { k.clone() } else { format!("{}.{}", field, k) };
+                interpolate(v, &child, issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `${VAR}` / `${VAR:-기본값}` 을 환경 변수 값으로 바꿉니다. `$${` 는 `${` 그대로 남깁니다.
+pub fn expand_env(input: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unclosed ${{ in {:?}", input))?;
+            let expr = &after[..end];
+            let (name, default) = match expr.split_once(":-") {
+                Some((n, d)) => (n, Some(d)),
+                None => (expr, None),
+            };
+            match (std::env::var(name), default) {
+                (Ok(v), _) => out.push_str(&v),
+                (Err(_), Some(d)) => out.push_str(d),
+                (Err(_), None) => {
+                    return Err(format!("environment variable {} is not set", name));
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// 설정 값들의 의미상 오류를 한 번에 모아 확인합니다.
+pub fn validate(path: &str, config: &Config) -> Result<(), ConfigError> {
+    let mut issues = vec![];
+    let mut issue = |field: String, reason: String| issues.push(ConfigIssue { field, reason });
+
+    // 저장 항목은 사이트 id 가 같은 사이트의 게시물을 받습니다 (예: mp 저장은 mp_low 사이트도 받음).
+    let site_ids: HashSet<String> = config
+        .sites
+        .iter()
+        .filter_map(|s| scrapers::for_site(s).ok())
+        .map(|s| s.site_id().to_string())
+        .collect();
+
+    for (i, site) in config.sites.iter().enumerate() {
+        if let Err(e) = scrapers::for_site(site) {
+            match site.selectors {
+                Some(_) => issue(format!("sites[{}].selectors", i), format!("{:#}", e)),
+                None => issue(
+                    format!("sites[{}].host", i),
+                    format!("{:#} (known hosts: {})", e, scrapers::known_hosts().join(", ")),
+                ),
+            }
+        }
+        if let Err(e) = Url::parse(&site.url) {
+            issue(format!("sites[{}].url", i), format!("{:?}: {}", site.url, e));
+        }
+        if let Err(e) = Schedule::from_site(site) {
+            issue(format!("sites[{}]", i), format!("{:#}", e));
+        }
+        if let Some(label) = &site.encoding
+            && !charset::is_known(label)
+        {
+            issue(format!("sites[{}].encoding", i), format!("unknown encoding: {:?}", label));
+        }
+        if let Some(name) = &site.timezone
+            && let Err(e) = posttime::check_timezone(name)
+        {
+            issue(format!("sites[{}].timezone", i), format!("{:?}: {}", name, e));
+        }
+        if let Some(spec) = &site.detail {
+            if spec.is_builtin() {
+                let builtin = scrapers::for_site(site).ok().and_then(|s| s.detail_spec());
+                if builtin.is_none() {
+                    issue(
+                        format!("sites[{}].detail", i),
+                        format!("no built-in detail selectors for {:?}; set them in the config", site.host),
+                    );
+                }
+            } else if let Err(e) = scrapers::detail::check(spec) {
+                issue(format!("sites[{}].detail", i), format!("{:#}", e));
+            }
+        }
+        if let Some(spec) = &site.comments {
+            let field = |name: &str| format!("sites[{}].comments.{}", i, name);
+            let supported = scrapers::for_site(site).is_ok_and(|s| s.supports_comments());
+            if !supported {
+                issue(
+                    format!("sites[{}].comments", i),
+                    format!("comment collection is not supported for {:?}", site.host),
+                );
+            }
+            if spec.json_path.is_empty() {
+                issue(field("json_path"), "must not be empty".to_string());
+            } else if config.saves.iter().any(|s| s.path() == spec.json_path) {
+                issue(field("json_path"), format!("{:?} is already used by a save", spec.json_path));
+            }
+            if spec.titles.is_empty() {
+                issue(field("titles"), "at least one title is required".to_string());
+            }
+            if spec.max_pages == 0 {
+                issue(field("max_pages"), "must be at least 1".to_string());
+            }
+        }
+        if let Some(pages) = &site.pagination {
+            let field = |name: &str| format!("sites[{}].pagination.{}", i, name);
+            if pages.max_pages == 0 {
+                issue(field("max_pages"), "must be at least 1".to_string());
+            }
+            match (&pages.page_param, &pages.next_selector) {
+                (Some(_), Some(_)) => issue(
+                    field("next_selector"),
+                    "use either page_param or next_selector, not both".to_string(),
+                ),
+                (None, None) if pages.max_pages > 1 => issue(
+                    field("page_param"),
+                    "page_param or next_selector is required".to_string(),
+                ),
+                _ => {}
+            }
+            if let Some(selector) = &pages.next_selector
+                && let Err(e) = pagination::check_selector(selector)
+            {
+                issue(field("next_selector"), format!("{:#}", e));
+            }
+        }
+    }
+
+    let profile_names = clients::profile_names(config);
+    let mut seen_profiles: HashMap<&str, usize> = HashMap::new();
+    for (i, profile) in config.profiles.iter().enumerate() {
+        match seen_profiles.get(profile.name.as_str()) {
+            Some(first) => issue(
+                format!("profiles[{}].name", i),
+                format!("{:?} is already used by profiles[{}]", profile.name, first),
+            ),
+            None => {
+                seen_profiles.insert(profile.name.as_str(), i);
+            }
+        }
+    }
+    if let Err(errors) = clients::build_all(config) {
+        for e in errors {
+            issue(e.field, e.reason);
+        }
+    }
+    let mut check_profile = |field: String, name: &Option<String>| {
+        if let Some(name) = name
+            && !profile_names.contains(name)
+        {
+            issue(
+                field,
+                format!("unknown profile {:?} (known: {})", name, profile_names.join(", ")),
+            );
+        }
+    };
+    for (i, site) in config.sites.iter().enumerate() {
+        check_profile(format!("sites[{}].profile", i), &site.profile);
+    }
+    for (i, down) in config.downs.iter().enumerate() {
+        check_profile(format!("downs[{}].profile", i), &down.profile);
+    }
+
+    let mut seen_limits: HashMap<&str, usize> = HashMap::new();
+    for (i, limit) in config.rate_limits.iter().enumerate() {
+        if limit.host.is_empty() {
+            issue(format!("rate_limits[{}].host", i), "must not be empty".to_string());
+        }
+        if !(limit.requests_per_sec.is_finite() && limit.requests_per_sec > 0.0) {
+            issue(
+                format!("rate_limits[{}].requests_per_sec", i),
+                format!("must be greater than 0, got {}", limit.requests_per_sec),
+            );
+        }
+        if limit.max_in_flight == 0 {
+            issue(format!("rate_limits[{}].max_in_flight", i), "must be at least 1".to_string());
+        }
+        match seen_limits.get(limit.host.as_str()) {
+            Some(first) => issue(
+                format!("rate_limits[{}].host", i),
+                format!("{:?} is already used by rate_limits[{}]", limit.host, first),
+            ),
+            None => {
+                seen_limits.insert(limit.host.as_str(), i);
+            }
+        }
+    }
+
+    if let Some(cache) = &config.response_cache
+        && cache.dir.is_empty()
+    {
+        issue("response_cache.dir".to_string(), "must not be empty".to_string());
+    }
+
+    if let Err(e) = Url::parse(&config.webdriver_url) {
+        issue("webdriver_url".to_string(), format!("{:?}: {}", config.webdriver_url, e));
+    }
+
+    let mut save_paths: HashMap<&str, usize> = HashMap::new();
+    for (i, save) in config.saves.iter().enumerate() {
+        let matched = scrapers::site_id_for(&save.host, &config.sites)
+            .is_some_and(|id| site_ids.contains(&id));
+        if !matched {
+            issue(
+                format!("saves[{}].host", i),
+                format!("no site feeds host {:?}", save.host),
+            );
+        }
+        match (save.json_path.is_empty(), &save.sqlite_path) {
+            (true, None) => issue(
+                format!("saves[{}]", i),
+                "one of json_path or sqlite_path is required".to_string(),
+            ),
+            (false, Some(_)) => issue(
+                format!("saves[{}]", i),
+                "json_path and sqlite_path cannot be used together".to_string(),
+            ),
+            (_, Some(path)) if path.is_empty() => issue(
+                format!("saves[{}].sqlite_path", i),
+                "must not be empty".to_string(),
+            ),
+            _ => {}
+        }
+        if let Some(days) = save.retention_days {
+            if save.sqlite_path.is_none() {
+                issue(
+                    format!("saves[{}].retention_days", i),
+                    "only applies to sqlite_path (JSON keeps 72 hours)".to_string(),
+                );
+            } else if days == 0 {
+                issue(format!("saves[{}].retention_days", i), "must be at least 1".to_string());
+            }
+        }
+        if save.backups > 0 && save.sqlite_path.is_some() {
+            issue(
+                format!("saves[{}].backups", i),
+                "only applies to json_path".to_string(),
+            );
+        }
+        // 여러 저장 항목이 한 데이터베이스를 함께 쓰는 것은 괜찮지만, JSON 파일은 서로 덮어씁니다.
+        if save.sqlite_path.is_none() && !save.json_path.is_empty() {
+            match save_paths.get(save.json_path.as_str()) {
+                Some(first) => issue(
+                    format!("saves[{}].json_path", i),
+                    format!("{:?} is already used by saves[{}]", save.json_path, first),
+                ),
+                None => {
+                    save_paths.insert(save.json_path.as_str(), i);
+                }
+            }
+        }
+    }
+
+    let mut feed_paths: HashMap<&str, usize> = HashMap::new();
+    for (i, feed) in config.feeds.iter().enumerate() {
+        let field = |name: &str| format!("feeds[{}].{}", i, name);
+        if feed.path.is_empty() {
+            issue(field("path"), "must not be empty".to_string());
+        } else if config.saves.iter().any(|s| s.path() == feed.path)
+            || config.sites.iter().any(|s| s.comments.as_ref().is_some_and(|c| c.json_path == feed.path))
+        {
+            issue(field("path"), format!("{:?} is already used by a save or comments", feed.path));
+        } else if let Some(first) = feed_paths.insert(feed.path.as_str(), i) {
+            issue(field("path"), format!("{:?} is already used by feeds[{}]", feed.path, first));
+        }
+        for host in feed.saves.iter().filter(|h| !config.saves.iter().any(|s| &s.host == *h)) {
+            issue(field("saves"), format!("no save with host {:?}", host));
+        }
+        if feed.max_items == 0 {
+            issue(field("max_items"), "must be at least 1".to_string());
+        }
+        if feed.link.is_none() && feed::default_link(config, feed).is_none() {
+            issue(field("link"), "required when no site feeds the first included save".to_string());
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError {
+            path: path.to_string(),
+            issues,
+        })
+    }
+}
+
+/// 두 설정 사이에서 추가/삭제/변경된 항목을 사람이 읽을 수 있는 줄로 돌려줍니다.
+pub fn diff(old: &Config, new: &Config) -> Vec<String> {
+    let mut lines = vec![];
+    diff_section(&mut lines, "site", &old.sites, &new.sites, |s| format!("{} {}", s.host, s.url));
+    diff_section(&mut lines, "save", &old.saves, &new.saves, |s| format!("{} -> {}", s.host, s.path()));
+    diff_section(&mut lines, "down", &old.downs, &new.downs, |d| format!("{} {:?}", d.host, d.title));
+    diff_section(&mut lines, "nick", &old.nicks, &new.nicks, |n| n.nick.clone());
+    diff_section(&mut lines, "profile", &old.profiles, &new.profiles, |p| p.name.clone());
+    diff_section(&mut lines, "rate_limit", &old.rate_limits, &new.rate_limits, |r| r.host.clone());
+    diff_section(&mut lines, "feed", &old.feeds, &new.feeds, |f| f.path.clone());
+    if old.webdriver_url != new.webdriver_url {
+        lines.push(format!("webdriver_url: {} -> {}", old.webdriver_url, new.webdriver_url));
+    }
+    if old.response_cache != new.response_cache {
+        lines.push(format!(
+            "response_cache: {:?} -> {:?}",
+            old.response_cache, new.response_cache
+        ));
+    }
+    if old.enable_download != new.enable_download {
+        lines.push(format!(
+            "enable_download: {} -> {}",
+            old.enable_download, new.enable_download
+        ));
+    }
+    lines
+}
+
+/// 새 설정에서 추가되었거나 정의가 바뀐 사이트
+pub fn changed_sites<'a>(old: &Config, new: &'a Config) -> Vec<&'a Site> {
+    new.sites
+        .iter()
+        .filter(|site| {
+            let value = serde_json::to_value(site).unwrap_or_default();
+            !old.sites
+                .iter()
+                .any(|prev| serde_json::to_value(prev).unwrap_or_default() == value)
+        })
+        .collect()
+}
+
+fn diff_section<T: Serialize>(
+    lines: &mut Vec<String>,
+    name: &str,
+    old: &[T],
+    new: &[T],
+    key: impl Fn(&T) -> String,
+) {
+    let index = |items: &[T]| -> BTreeMap<String, serde_json::Value> {
+        items
+            .iter()
+            .map(|v| (key(v), serde_json::to_value(v).unwrap_or_default()))
+            .collect()
+    };
+    let (old, new) = (index(old), index(new));
+    for (k, v) in &new {
+        match old.get(k) {
+            None => lines.push(format!("{} added: {}", name, k)),
+            Some(prev) if prev != v => lines.push(format!("{} changed: {}", name, k)),
+            Some(_) => {}
+        }
+    }
+    for k in old.keys().filter(|k| !new.contains_key(*k)) {
+        lines.push(format!("{} removed: {}", name, k));
+    }
+}
+
+/// 설정 파일의 수정 시각을 확인해 바뀐 경우에만 다시 읽습니다.
+///
+/// 새 설정이 잘못되었으면 마지막으로 정상이었던 설정을 유지합니다.
+pub struct Watcher {
+    path: String,
+    modified: Option<SystemTime>,
+    current: Arc<Config>,
+}
+
+async fn modified_at(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).await.and_then(|m| m.modified()).ok()
+}
+
+impl Watcher {
+    pub async fn new(path: &str, config: Arc<Config>) -> Self {
+        Watcher {
+            path: path.to_string(),
+            modified: modified_at(path).await,
+            current: config,
+        }
+    }
+
+    /// 설정이 바뀌었고 검증을 통과했으면 새 설정을 돌려줍니다.
+    pub async fn poll(&mut self) -> Option<Arc<Config>> {
+        let modified = modified_at(&self.path).await;
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        let config = match load(&self.path).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Config reload rejected, keeping last good config: {}", e);
+                return None;
+            }
+        };
+        let changes = diff(&self.current, &config);
+        if changes.is_empty() {
+            return None;
+        }
+        for line in &changes {
+            info!("config {}", line);
+            println!("config {}", line);
+        }
+        self.current = Arc::new(config);
+        Some(Arc::clone(&self.current))
+    }
+}
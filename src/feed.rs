@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::path::Path;
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::warn;
+use crate::models::{Config, Feed, FeedFormat, Post};
+use crate::{scrapers, storage, utils};
+
+// 설정에 제목이 없을 때의 피드 제목
+const DEFAULT_TITLE: &str = "textminer";
+
+/// XML 본문/속성에 넣을 수 있게 특수 문자를 바꾸고, XML 에 쓸 수 없는 제어 문자는 버립니다.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 게시물의 작성 시각. 모르면 정렬 기준 시각(`timestamp`)입니다.
+fn post_time(post: &Post) -> DateTime<Utc> {
+    DateTime::from_timestamp(post.posted_at.unwrap_or(post.timestamp), 0).unwrap_or_default()
+}
+
+fn render_rss(title: &str, link: &str, posts: &[Post], now: DateTime<Utc>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+    out.push_str(&format!("<title>{}</title>\n", escape(title)));
+    out.push_str(&format!("<link>{}</link>\n", escape(link)));
+    out.push_str(&format!("<description>{}</description>\n", escape(title)));
+    out.push_str(&format!("<lastBuildDate>{}</lastBuildDate>\n", now.to_rfc2822()));
+    for post in posts {
+        out.push_str("<item>\n");
+        out.push_str(&format!("<title>{}</title>\n", escape(&post.title)));
+        out.push_str(&format!("<link>{}</link>\n", escape(&post.link)));
+        out.push_str(&format!("<guid isPermaLink=\"false\">{}</guid>\n", escape(&post.id)));
+        out.push_str(&format!("<pubDate>{}</pubDate>\n", post_time(post).to_rfc2822()));
+        out.push_str(&format!("<category>{}</category>\n", escape(&post.label)));
+        out.push_str("</item>\n");
+    }
+    out.push_str("</channel>\n</rss>\n");
+    out
+}
+
+fn render_atom(title: &str, link: &str, posts: &[Post], now: DateTime<Utc>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    out.push_str(&format!("<title>{}</title>\n", escape(title)));
+    out.push_str(&format!("<id>{}</id>\n", escape(link)));
+    out.push_str(&format!("<link href=\"{}\"/>\n", escape(link)));
+    out.push_str(&format!("<updated>{}</updated>\n", now.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    out.push_str(&format!("<author><name>{}</name></author>\n", DEFAULT_TITLE));
+    for post in posts {
+        let time = post_time(post).to_rfc3339_opts(SecondsFormat::Secs, true);
+        out.push_str("<entry>\n");
+        out.push_str(&format!("<title>{}</title>\n", escape(&post.title)));
+        out.push_str(&format!("<link href=\"{}\"/>\n", escape(&post.link)));
+        out.push_str(&format!("<id>{}</id>\n", escape(&post.link)));
+        out.push_str(&format!("<published>{}</published>\n", time));
+        out.push_str(&format!("<updated>{}</updated>\n", time));
+        out.push_str(&format!("<category term=\"{}\"/>\n", escape(&post.label)));
+        if !post.author.is_empty() {
+            out.push_str(&format!("<author><name>{}</name></author>\n", escape(&post.author)));
+        }
+        out.push_str("</entry>\n");
+    }
+    out.push_str("</feed>\n");
+    out
+}
+
+/// 피드 하나를 만들어 씁니다.
+async fn export(config: &Config, feed: &Feed) -> Result<()> {
+    let saves: Vec<_> = config
+        .saves
+        .iter()
+        .filter(|s| includes(feed, &s.host))
+        .collect();
+
+    let mut posts = vec![];
+    let mut ids = HashSet::new();
+    for save in &saves {
+        let scraper = match scrapers::for_save(&save.host, &config.sites) {
+            Some(v) => v,
+            None => continue,
+        };
+        let recent = storage::open(save, scraper)
+            .recent(feed.max_items)
+            .await
+            .context(format!("Failed to read {}", save.path()))?;
+        posts.extend(recent.into_iter().filter(|p| ids.insert(p.id.clone())));
+    }
+    posts.sort_by_key(|p| std::cmp::Reverse(p.timestamp));
+    posts.truncate(feed.max_items);
+
+    let title = feed.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    let link = feed
+        .link
+        .clone()
+        .or_else(|| default_link(config, feed))
+        .context(format!("feed {} has no link", feed.path))?;
+    let now = Utc::now();
+    let xml = match feed.format {
+        FeedFormat::Rss => render_rss(title, &link, &posts, now),
+        FeedFormat::Atom => render_atom(title, &link, &posts, now),
+    };
+    utils::write_atomic(Path::new(&feed.path), xml.as_bytes())
+        .await
+        .context(format!("Failed to write feed: {}", feed.path))
+}
+
+/// `feeds[].link` 가 없을 때 쓰는 주소. 첫 번째 저장 항목에 게시물을 보내는 사이트의 주소입니다.
+///
+/// 저장 host 와 사이트 host 가 다를 수 있으므로(mp 저장 항목 ← mp_low 사이트) 사이트 id 로 찾습니다.
+pub fn default_link(config: &Config, feed: &Feed) -> Option<String> {
+    let save = config.saves.iter().find(|s| includes(feed, &s.host))?;
+    let site_id = scrapers::site_id_for(&save.host, &config.sites)?;
+    config
+        .sites
+        .iter()
+        .find(|s| scrapers::for_site(s).is_ok_and(|scraper| scraper.site_id() == site_id))
+        .map(|s| s.url.clone())
+}
+
+fn includes(feed: &Feed, host: &str) -> bool {
+    feed.saves.is_empty() || feed.saves.iter().any(|s| s == host)
+}
+
+/// 모든 피드를 다시 만듭니다. 실패는 경고로 남깁니다.
+pub async fn export_all(config: &Config) {
+    for feed in config.feeds.iter() {
+        if let Err(e) = export(config, feed).await {
+            warn!("{:#}", e);
+        }
+    }
+}
+
+/// 사이트 id 가 `site_id` 인 저장 항목을 담는 피드만 다시 만듭니다.
+pub async fn export_for_site(config: &Config, site_id: &str) {
+    let hosts: Vec<&str> = config
+        .saves
+        .iter()
+        .filter(|s| scrapers::site_id_for(&s.host, &config.sites).as_deref() == Some(site_id))
+        .map(|s| s.host.as_str())
+        .collect();
+    for feed in config.feeds.iter().filter(|f| hosts.iter().any(|h| includes(f, h))) {
+        if let Err(e) = export(config, feed).await {
+            warn!("{:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post() -> Post {
+        Post {
+            id: "dc:1".to_string(),
+            title: "A & B <c> \"d\" 'e'\u{1}".to_string(),
+            link: "https://example.com/view?id=1&no=2".to_string(),
+            author: "닉".to_string(),
+            timestamp: 1729213200,
+            posted_at: Some(1729209600),
+            label: "디시".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1729216800, 0).unwrap()
+    }
+
+    fn parse_config(value: serde_json::Value) -> Config {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn escapes_markup_and_drops_control_chars() {
+        assert_eq!(escape("A & B <c> \"d\" 'e'"), "A &amp; B &lt;c&gt; &quot;d&quot; &apos;e&apos;");
+        assert_eq!(escape("a\u{0}b\u{1b}c\td\n"), "abc\td\n");
+    }
+
+    #[test]
+    fn renders_rss() {
+        let xml = render_rss("모음 & 목록", "https://example.com/", &[post()], now());
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">"));
+        assert!(xml.contains("<title>모음 &amp; 목록</title>"));
+        assert!(xml.contains("<lastBuildDate>Fri, 18 Oct 2024 02:00:00 +0000</lastBuildDate>"));
+        assert!(xml.contains("<title>A &amp; B &lt;c&gt; &quot;d&quot; &apos;e&apos;</title>"));
+        assert!(xml.contains("<link>https://example.com/view?id=1&amp;no=2</link>"));
+        assert!(xml.contains("<guid isPermaLink=\"false\">dc:1</guid>"));
+        assert!(xml.contains("<pubDate>Fri, 18 Oct 2024 00:00:00 +0000</pubDate>"));
+        assert!(xml.contains("<category>디시</category>"));
+        assert!(xml.ends_with("</channel>\n</rss>\n"));
+    }
+
+    #[test]
+    fn renders_atom() {
+        let mut unknown = post();
+        unknown.posted_at = None;
+        unknown.author.clear();
+        let xml = render_atom("모음", "https://example.com/", &[post(), unknown], now());
+        assert!(xml.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(xml.contains("<updated>2024-10-18T02:00:00Z</updated>"));
+        assert!(xml.contains("<link href=\"https://example.com/view?id=1&amp;no=2\"/>"));
+        assert!(xml.contains("<published>2024-10-18T00:00:00Z</published>"));
+        // 작성 시각을 모르면 timestamp 를 씁니다.
+        assert!(xml.contains("<published>2024-10-18T01:00:00Z</published>"));
+        assert!(xml.contains("<category term=\"디시\"/>"));
+        assert_eq!(xml.matches("<author><name>닉</name></author>").count(), 1);
+        assert_eq!(xml.matches("<entry>").count(), 2);
+        assert!(xml.ends_with("</feed>\n"));
+    }
+
+    #[test]
+    fn default_link_follows_site_id() {
+        let config = parse_config(serde_json::json!({
+            "sites": [{ "host": "mp_low", "url": "https://mlbpark.donga.com/mp/b.php?b=bullpen" }],
+            "saves": [{ "host": "mp", "json_path": "mp.json" }],
+            "feeds": [{ "path": "mp.xml" }]
+        }));
+        assert_eq!(
+            default_link(&config, &config.feeds[0]).as_deref(),
+            Some("https://mlbpark.donga.com/mp/b.php?b=bullpen")
+        );
+
+        let config = parse_config(serde_json::json!({
+            "saves": [{ "host": "dc", "json_path": "dc.json" }],
+            "feeds": [{ "path": "dc.xml" }]
+        }));
+        assert_eq!(default_link(&config, &config.feeds[0]), None);
+    }
+}
@@ -1,9 +1,10 @@
 use thirtyfour::prelude::*;
 use std::time::Duration;
 use anyhow::{Result, Context};
+use crate::ratelimit;
 
 // WebDriver configuration constants
-const WEBDRIVER_URL: &str = "http://localhost:4444";
+pub const DEFAULT_WEBDRIVER_URL: &str = "http://localhost:4444";
 const BODY_LOAD_TIMEOUT_SECS: u64 = 10;
 const BODY_LOAD_POLL_MS: u64 = 500;
 const SCROLL_DISTANCE: i32 = 200;
@@ -13,11 +14,14 @@ const CONTENT_LOAD_WAIT_SECS: u64 = 3;
 const IMAGE_LOAD_TIMEOUT_SECS: u64 = 5;
 
 /// Firefox로 페이지 크롤링 (레이지 로딩 지원)
-pub async fn get_html(url: &str) -> Result<String> {
+pub async fn get_html(url: &str, webdriver_url: &str) -> Result<String> {
+    // 브라우저가 페이지를 불러오는 동안 호스트별 속도 제한 허가를 들고 있습니다.
+    let _permit = ratelimit::acquire(url).await;
+
     // WebDriver 연결
     let caps = DesiredCapabilities::firefox();
-    let driver = WebDriver::new(WEBDRIVER_URL, caps).await
-        .context(format!("Failed to connect to WebDriver at {}", WEBDRIVER_URL))?;
+    let driver = WebDriver::new(webdriver_url, caps).await
+        .context(format!("Failed to connect to WebDriver at {}", webdriver_url))?;
 
     // 페이지 이동
     driver.goto(url).await.context(format!("Failed to navigate to URL: {}", url))?;
@@ -2,166 +2,158 @@ use anyhow::{Context, Result};
 use chrono::Utc;
 use chrono_tz::Asia::Seoul;
 use futures::future::join_all;
-use std::collections::HashSet;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use url::Url;
 
+mod cache;
+mod charset;
+mod cli;
+mod clients;
+mod config;
+mod feed;
 mod foxfox;
 mod models;
+mod posttime;
+mod ratelimit;
+mod robots;
+mod scheduler;
 mod scrapers;
+mod storage;
 mod utils;
 
-use models::{Config, Down, Images, List, Nick, Site};
-use scrapers::{dc, fm, mp};
-
-// Configuration file path
-const CONFIG_PATH: &str = "./config.json";
+use cli::Command;
+use models::{CommentThread, Config, DetailSpec, Down, Images, Post, PostDetail, Site};
+use scrapers::SeenIds;
+use storage::MAX_POST_AGE_SECS;
 
 // Timing constants (in seconds)
-const SCRAPE_INTERVAL_SECS: u64 = 300; // 5 minutes between scraping cycles
-const REQUEST_DELAY_MS: u64 = 500; // Delay between concurrent requests
-const NEW_MARKER_AGE_SECS: i64 = 28800; // 8 hours - posts newer than this keep "new" flag
-const MAX_POST_AGE_SECS: i64 = 259200; // 72 hours - posts older than this are filtered out
-
-async fn scrape_site(site: Site, nick_list: &[Nick]) -> Vec<List> {
-    match site.host.as_str() {
-        "dc" => {
-            // 파일 다운로드가 아닌 일반 스크랩 시에는 WebDriver를 사용하지 않습니다.
-            let html = utils::get_text_response(&site.url).await;
+const CONFIG_POLL_SECS: u64 = 5; // How often the config file is checked for changes
 
-            if !html.is_empty() {
-                let (results, _logs) = dc::parse_dc(&html, &site.url, nick_list);
-                //for log_link in logs {
-                //    //utils::logger(&log_link).await;
-                //}
-                results.unwrap_or_default()
-            } else {
-                vec![]
-            }
-        }
-        "fm" => {
-            let html = utils::get_text_response(&site.url).await;
-            if !html.is_empty() {
-                fm::parse_fm(&html).unwrap_or_default()
-            } else {
-                vec![]
-            }
-        }
-        "mp" => {
-            let html = utils::get_text_response_bot(&site.url).await;
-            if !html.is_empty() {
-                mp::parse_mp(&html).unwrap_or_default()
-            } else {
-                vec![]
-            }
-        }
-        "mp_low" => {
-            let html = utils::get_text_response_bot(&site.url).await;
-            if !html.is_empty() {
-                mp::parse_mp_part_low(&html).unwrap_or_default()
-            } else {
-                vec![]
-            }
-        }
-        _ => {
-            println!("not matched site: {}", site.host);
-            utils::logger(&format!("not matched site: {}", site.host));
-            vec![]
-        }
-    }
+lazy_static! {
+    static ref SAVE_LOCKS: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>> =
+        Mutex::new(HashMap::new());
 }
 
-async fn run_scraping_cycle() -> Result<()> {
-    let config_json = utils::file_read_to_json(CONFIG_PATH)
-        .await
-        .unwrap_or_default();
-    let config: Config = serde_json::from_value(config_json).unwrap_or_default();
-
-    let site_list = config.sites;
-    let mut save_list = config.saves;
-    let down_list = config.downs;
-    let nick_list = config.nicks;
-
-    let mut dc_list: Vec<List> = vec![];
-    let mut fm_list: Vec<List> = vec![];
-    let mut mp_list: Vec<List> = vec![];
-
-    let mut dc_down_list: Vec<List> = vec![];
-    let mut down_image_list = vec![];
-
-    let nick_list = std::sync::Arc::new(nick_list);
+/// 저장 파일별 잠금. 같은 저장 파일을 쓰는 사이트(mp / mp_low)가 동시에 병합하지 않도록 합니다.
+fn save_lock(path: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = SAVE_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    Arc::clone(locks.entry(path.to_string()).or_default())
+}
 
-    let scrape_tasks: Vec<_> = site_list
-        .into_iter()
-        .enumerate()
-        .map(|(i, site)| {
-            let nick_list = std::sync::Arc::clone(&nick_list);
-            tokio::spawn(async move {
-                if i > 0 {
-                    let delay = std::time::Duration::from_millis((i as u64) * REQUEST_DELAY_MS);
-                    tokio::time::sleep(delay).await;
+/// 사이트 하나를 수집하고, 사이트 id 가 같은 저장 파일에 병합한 뒤 새 게시물의 이미지를 내려받습니다.
+async fn process_site(config: Arc<Config>, site: Site) -> Result<()> {
+    let scraper = scrapers::for_site(&site)?;
+    let site_id = scraper.site_id().to_string();
+    let site_profile = site
+        .profile
+        .clone()
+        .unwrap_or_else(|| scraper.default_profile().to_string());
+    let detail_spec = scrapers::detail_spec_for(&site, scraper.as_ref());
+    let saved = storage::SavedIds::new(&config, &site_id);
+    let mut result = match scraper.scrape(&site, &config.nicks, &saved).await? {
+        Some(v) => v,
+        None => {
+            info!("{} not modified, skipped", site.host);
+            return Ok(());
+        }
+    };
+    let scraped = &mut result.posts;
+    // 작성 시각이 이미 만료 기준을 넘은 글은 저장해도 다음 주기에 지워지므로 받지 않습니다.
+    let _stamp = Utc::now().with_timezone(&Seoul).timestamp();
+    scraped.retain(|x| _stamp - x.timestamp < MAX_POST_AGE_SECS);
+    scraped.sort_by_key(|x| std::cmp::Reverse(x.timestamp));
+
+    if let Some(spec) = &detail_spec {
+        let ids: Vec<String> = scraped.iter().map(|p| p.id.clone()).collect();
+        let seen = saved.known(&ids).await;
+        for post in scraped.iter_mut().filter(|p| !seen.contains(&p.id)) {
+            if let Some(detail) = fetch_detail(&site, spec, &site_profile, &post.link).await {
+                if post.images.is_empty() {
+                    post.images = detail.images.first().cloned().unwrap_or_default();
                 }
-                scrape_site(site, &nick_list).await
-            })
-        })
-        .collect();
-
-    let scrape_results: Vec<Vec<List>> = join_all(scrape_tasks)
-        .await
-        .into_iter()
-        .map(|r| r.unwrap_or_default())
-        .collect();
-
-    //중복 제거안해도 나중에 정리됨
-
-    for posts in scrape_results {
-        for post in posts {
-            match post.more.as_str() {
-                "디시" => dc_list.push(post),
-                "펨코" => fm_list.push(post),
-                "엠팍" => mp_list.push(post),
-                _ => {}
+                if post.author.is_empty() {
+                    post.author = detail.author.clone();
+                }
+                post.flags.has_images |= !detail.images.is_empty();
+                post.detail = Some(detail);
             }
         }
     }
 
-    // 시간순 정렬 (최신순) - scrape 결과가 비동기 수집되므로 이 단계에서 보정
-    dc_list.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    fm_list.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    mp_list.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-
-    for _save in save_list.iter_mut() {
-        match _save.host.as_ref() {
-            "dc" => {
-                let _loadfile = load_file_to_list(&_save.json_path).await;
-                dc_down_list = newer_to_list(&dc_list, &_loadfile);
-                let merged = merge_to_list(&dc_list, &_loadfile);
-                let save_json =
-                    serde_json::to_value(merged).context("Failed to serialize dc list")?;
-                utils::file_save_from_json(&_save.json_path, &save_json).await?;
-            }
-            "fm" => {
-                let _loadfile = load_file_to_list(&_save.json_path).await;
-                let merged = merge_to_list(&fm_list, &_loadfile);
-                let save_json =
-                    serde_json::to_value(merged).context("Failed to serialize fm list")?;
-                utils::file_save_from_json(&_save.json_path, &save_json).await?;
+    // (Save host, 새로 발견된 게시물). 같은 사이트를 여러 저장소에 쌓아도 게시물마다 한 번만 받습니다.
+    let mut new_posts: Vec<(&str, Post)> = vec![];
+    let mut new_ids: HashSet<String> = HashSet::new();
+    // (프로필, 받을 이미지)
+    let mut down_image_list: Vec<(&str, Images)> = vec![];
+
+    for (_save, store) in storage::for_site_id(&config, &site_id) {
+        let lock = save_lock(_save.path());
+        let _guard = lock.lock().await;
+        let added = store
+            .merge(&result.posts)
+            .await
+            .context(format!("Failed to save {} posts", _save.host))?;
+        new_posts.extend(
+            added
+                .into_iter()
+                .filter(|post| new_ids.insert(post.id.clone()))
+                .map(|post| (_save.host.as_str(), post)),
+        );
+    }
+    // 저장에 실패하면 검증자를 남기지 않아, 다음 주기에 같은 목록을 다시 받아 저장합니다.
+    result.commit();
+    let scraped = result.posts;
+
+    if let Some(spec) = &site.comments {
+        // 처음 보는 게시물과 목록의 댓글 수가 지난번과 달라진 게시물만 다시 읽습니다.
+        let stored = stored_comment_counts(&spec.json_path).await;
+        let targets = scraped.iter().filter(|p| {
+            spec.titles.iter().any(|t| p.title.contains(t))
+                && match stored.get(&p.link) {
+                    None => true,
+                    Some(count) => p.comment_count.is_some_and(|c| c != *count),
+                }
+        });
+        for post in targets {
+            if site.respect_robots && !robots::allowed(&post.link, &site_profile).await {
+                continue;
             }
-            "mp" => {
-                let _loadfile = load_file_to_list(&_save.json_path).await;
-                let merged = merge_to_list(&mp_list, &_loadfile);
-                let save_json =
-                    serde_json::to_value(merged).context("Failed to serialize mp list")?;
-                utils::file_save_from_json(&_save.json_path, &save_json).await?;
+            let collected = scraper
+                .collect_comments(&post.link, &site, &site_profile, spec.max_pages)
+                .await;
+            match collected {
+                Ok((total, comments)) => {
+                    let thread = CommentThread {
+                        link: post.link.clone(),
+                        title: post.title.clone(),
+                        fetched_at: Utc::now().with_timezone(&Seoul).timestamp(),
+                        total,
+                        list_count: post.comment_count,
+                        comments,
+                    };
+                    save_comments(&spec.json_path, thread).await?;
+                }
+                Err(e) => warn!("comments failed for {}: {:#}", post.link, e),
             }
-            _ => {}
         }
     }
 
     if config.enable_download {
-        for _downlink in dc_down_list.iter_mut() {
-            if let Some(down_cfg) = find_download_target(&_downlink.title, &down_list) {
+        for (host, _downlink) in new_posts.iter() {
+            let scraper = match scrapers::find(host) {
+                Some(v) => v,
+                None => continue,
+            };
+            if let Some(down_cfg) = find_download_target(host, &_downlink.title, &config.downs) {
                 let path = &down_cfg.path;
+                let profile = down_cfg.profile.as_deref().unwrap_or(&site_profile);
+                if site.respect_robots && !robots::allowed(&_downlink.link, profile).await {
+                    continue;
+                }
                 let ho_url = Url::parse(&_downlink.link).context("Failed to parse downlink URL")?;
                 let host = format!(
                     "{}://{}",
@@ -170,107 +162,243 @@ async fn run_scraping_cycle() -> Result<()> {
                 );
 
                 let html = if down_cfg.use_webdriver {
-                    foxfox::get_html(&_downlink.link).await.unwrap_or_default()
+                    foxfox::get_html(&_downlink.link, &config.webdriver_url)
+                        .await
+                        .unwrap_or_default()
                 } else {
-                    utils::get_text_response(&_downlink.link).await
+                    utils::get_text_response(&_downlink.link, profile, site.encoding.as_deref())
+                        .await
+                        .unwrap_or_else(|e| {
+                            warn!("{}", e);
+                            String::new()
+                        })
                 };
 
                 if !html.is_empty() {
-                    let mut _list: Vec<Images> =
-                        dc::parse_dcimage(&html, path, &_downlink.title, &host)?;
-                    down_image_list.append(&mut _list);
+                    let _list: Vec<Images> =
+                        scraper.parse_images(&html, path, &_downlink.title, &host)?;
+                    down_image_list.extend(_list.into_iter().map(|img| (profile, img)));
                 }
             }
         }
     }
 
     if config.enable_download {
-        for _down in down_image_list.iter_mut() {
-            let data = utils::get_byte_response(&_down.link, &_down.refferer).await;
-            if !data.is_empty() {
-                let path = format!("{}/{}", &_down.path, &_down.subpath);
-                let _ = utils::make_file(&path, &_down.file_name, &data).await;
+        for (profile, _down) in down_image_list.iter() {
+            if site.respect_robots && !robots::allowed(&_down.link, profile).await {
+                continue;
+            }
+            match utils::get_byte_response(&_down.link, &_down.refferer, profile).await {
+                Ok(data) if !data.is_empty() => {
+                    let path = format!("{}/{}", &_down.path, &_down.subpath);
+                    let _ = utils::make_file(&path, &_down.file_name, &data).await;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("{}", e),
             }
         }
     }
 
-    println!("End Of job");
+    println!("End Of job: {}", site.host);
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let mut interval =
-        tokio::time::interval(tokio::time::Duration::from_secs(SCRAPE_INTERVAL_SECS));
-    loop {
-        interval.tick().await;
-        if let Err(e) = run_scraping_cycle().await {
-            //utils::logger(&format!("Scraping cycle failed: {}", e)).await;
-            eprintln!("Scraping cycle failed: {}", e);
+/// 새 게시물의 상세 페이지를 받아 읽습니다. 실패하면 경고를 남기고 `None` 입니다.
+async fn fetch_detail(site: &Site, spec: &DetailSpec, profile: &str, link: &str) -> Option<PostDetail> {
+    if site.respect_robots && !robots::allowed(link, profile).await {
+        return None;
+    }
+    let html = match utils::get_text_response(link, profile, site.encoding.as_deref()).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("{}", e);
+            return None;
+        }
+    };
+    match scrapers::detail::parse(spec, &html, link) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            warn!("detail parse failed for {}: {:#}", link, e);
+            None
         }
     }
 }
 
-fn find_download_target<'a>(_title: &str, down_list: &'a [Down]) -> Option<&'a Down> {
-    for _downtarget in down_list {
-        if _title.contains(&_downtarget.title) {
-            return Some(_downtarget);
+/// 사이트 하나를 수집한 뒤 그 사이트의 게시물을 담는 피드를 다시 만듭니다. (데몬 모드)
+async fn process_site_and_feeds(config: Arc<Config>, site: Site) -> Result<()> {
+    let site_id = scrapers::for_site(&site)?.site_id().to_string();
+    process_site(Arc::clone(&config), site).await?;
+    feed::export_for_site(&config, &site_id).await;
+    Ok(())
+}
+
+/// 모든 사이트를 한 번씩 수집하고 피드를 다시 만듭니다.
+async fn run_scraping_cycle(config: Arc<Config>) -> Result<()> {
+    let tasks = config
+        .sites
+        .iter()
+        .map(|site| process_site(Arc::clone(&config), site.clone()));
+    let mut failed = 0;
+    for result in join_all(tasks).await {
+        if let Err(e) = result {
+            error!("Scraping cycle failed: {:#}", e);
+            failed += 1;
         }
     }
-    None
+    feed::export_all(&config).await;
+    println!("End Of job");
+    if failed > 0 {
+        anyhow::bail!("{} site(s) failed", failed);
+    }
+    Ok(())
 }
 
-async fn load_file_to_list(path: &str) -> Vec<List> {
-    if Path::new(path).exists() {
-        let load_json = utils::file_read_to_json(path).await.unwrap_or_default();
-        let load_list: Vec<List> = serde_json::from_value(load_json).unwrap_or_default();
-        let _stamp = Utc::now().with_timezone(&Seoul).timestamp();
+/// 사이트마다 일정대로 계속 수집하고, 설정 파일이 바뀌면 새 설정으로 다시 띄웁니다.
+async fn run(config_path: &str) -> Result<()> {
+    let config = Arc::new(config::load(config_path).await?);
+    clients::install(&config)?;
+    ratelimit::install(&config);
+    cache::install(&config);
+    let mut scheduler = scheduler::Scheduler::start(Arc::clone(&config), process_site_and_feeds);
+    let mut current = Arc::clone(&config);
+    let mut watcher = config::Watcher::new(config_path, config).await;
 
-        let mut seen_links = HashSet::new();
-        load_list
-            .into_iter()
-            .filter_map(|mut x| {
-                if _stamp - x.timestamp > NEW_MARKER_AGE_SECS {
-                    x.new = false;
-                }
-                if (_stamp - x.timestamp) < MAX_POST_AGE_SECS && seen_links.insert(x.link.clone()) {
-                    Some(x)
-                } else {
-                    None
-                }
-            })
-            .collect()
-    } else {
-        vec![]
+    let mut interval =
+        tokio::time::interval(tokio::time::Duration::from_secs(CONFIG_POLL_SECS));
+    loop {
+        interval.tick().await;
+        if let Some(config) = watcher.poll().await {
+            if let Err(e) = clients::install(&config) {
+                warn!("Keeping previous HTTP clients: {:#}", e);
+            }
+            ratelimit::install(&config);
+            cache::install(&config);
+            // 정의가 바뀐 사이트는 목록이 그대로여도 새 정의로 다시 파싱하도록 검증자를 지웁니다.
+            for site in config::changed_sites(&current, &config) {
+                utils::forget_validators(&site.url);
+            }
+            current = Arc::clone(&config);
+            let restarted = scheduler.reload(config);
+            utils::logger(&format!("config reloaded, {} site schedule(s) restarted", restarted));
+        }
     }
 }
 
-fn newer_to_list(a: &[List], b: &[List]) -> Vec<List> {
-    let existing_links: HashSet<&str> = b.iter().map(|item| item.link.as_str()).collect();
+async fn check_config(config_path: &str) -> Result<()> {
+    let config = config::load(config_path).await?;
+    println!(
+        "{}: OK ({} sites, {} saves, {} downs, {} nicks)",
+        config_path,
+        config.sites.len(),
+        config.saves.len(),
+        config.downs.len(),
+        config.nicks.len()
+    );
+    Ok(())
+}
 
-    a.iter()
-        .filter(|item| !existing_links.contains(item.link.as_str()))
+/// 저장해 둔 HTML 파일을 파서에 넣어 결과 게시물을 출력합니다. 선택자 문제를 오프라인에서 확인할 때 씁니다.
+///
+/// 설정 파일에 같은 host 의 사이트가 있으면 그 정의(선택자, URL, 닉네임 필터)를 사용합니다.
+async fn parse_file(config_path: &str, host: &str, html_path: &str) -> Result<()> {
+    let config = if Path::new(config_path).exists() {
+        config::load(config_path).await?
+    } else {
+        Config::default()
+    };
+    let site = config
+        .sites
+        .iter()
+        .find(|s| s.host == host)
         .cloned()
-        .collect()
+        .unwrap_or_else(|| Site {
+            host: host.to_string(),
+            ..Default::default()
+        });
+    let scraper = scrapers::for_site(&site)?;
+    let html = tokio::fs::read_to_string(html_path)
+        .await
+        .context(format!("Failed to read HTML file: {}", html_path))?;
+    let posts = scraper.parse_page(&html, &site, &config.nicks)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&posts).context("Failed to serialize posts")?
+    );
+    eprintln!("{} posts parsed by {}", posts.len(), scraper.host());
+    Ok(())
 }
 
-fn merge_to_list(a: &[List], b: &[List]) -> Vec<List> {
-    let mut result = Vec::new();
-    let mut seen_links = HashSet::new();
+/// 저장해 둔 디시 댓글 응답(JSON)을 읽어 댓글 목록을 출력합니다.
+async fn parse_comments(json_path: &str) -> Result<()> {
+    let json = tokio::fs::read_to_string(json_path)
+        .await
+        .context(format!("Failed to read comment file: {}", json_path))?;
+    let now = posttime::now_in(posttime::DEFAULT_TIMEZONE);
+    let (total, comments) = scrapers::dc_comments::parse_dc_comments(&json, &now)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&comments).context("Failed to serialize comments")?
+    );
+    eprintln!("{} comments parsed (total_cnt {})", comments.len(), total);
+    Ok(())
+}
 
-    // 먼저 b 리스트의 항목을 추가하면서 링크를 추적합니다.
-    for item in b {
-        if seen_links.insert(item.link.as_str()) {
-            result.push(item.clone());
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = match cli::parse(std::env::args().skip(1)) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
         }
-    }
-
-    for item in a {
-        if seen_links.insert(item.link.as_str()) {
-            result.push(item.clone());
+    };
+    // 데몬/cron 실행에서만 파일 로그를 남깁니다.
+    let _logger = match cli.command {
+        Command::Run | Command::Once => Some(utils::init_logger()?),
+        _ => None,
+    };
+    match cli.command {
+        Command::Run => run(&cli.config).await,
+        Command::Once => {
+            let config = Arc::new(config::load(&cli.config).await?);
+            clients::install(&config)?;
+            ratelimit::install(&config);
+            cache::install(&config);
+            run_scraping_cycle(config).await
+        }
+        Command::CheckConfig => check_config(&cli.config).await,
+        Command::ParseFile { host, html } => parse_file(&cli.config, &host, &html).await,
+        Command::ParseComments { json } => parse_comments(&json).await,
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
         }
     }
+}
+
+fn find_download_target<'a>(host: &str, _title: &str, down_list: &'a [Down]) -> Option<&'a Down> {
+    down_list
+        .iter()
+        .find(|d| d.host == host && _title.contains(&d.title))
+}
+
+/// 댓글 파일에 저장된 게시물별 댓글 수. 목록의 댓글 수를 남기지 않은 항목은 전체 댓글 수입니다.
+async fn stored_comment_counts(path: &str) -> HashMap<String, u32> {
+    let threads: Vec<CommentThread> = utils::read_json_file(path).await.ok().flatten().unwrap_or_default();
+    threads
+        .into_iter()
+        .map(|t| (t.link, t.list_count.unwrap_or(t.total)))
+        .collect()
+}
 
-    result.sort_by(|x, y| y.timestamp.cmp(&x.timestamp));
-    result
+/// 댓글 파일에서 같은 게시물의 이전 댓글을 새 목록으로 바꾸고, 오래된 게시물의 댓글은 지웁니다.
+async fn save_comments(path: &str, thread: CommentThread) -> Result<()> {
+    let lock = save_lock(path);
+    let _guard = lock.lock().await;
+    let mut threads: Vec<CommentThread> = utils::read_json_file(path).await?.unwrap_or_default();
+    threads.retain(|t| t.link != thread.link && thread.fetched_at - t.fetched_at < MAX_POST_AGE_SECS);
+    threads.push(thread);
+    threads.sort_by_key(|t| std::cmp::Reverse(t.fetched_at));
+    let save_json = serde_json::to_value(threads).context("Failed to serialize comments")?;
+    utils::file_save_from_json(path, &save_json).await
 }
@@ -1,35 +1,524 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// 수집한 게시물 하나
+///
+/// `id` 는 `{site}:{board}:{post_no}` 형식이라 링크의 쿼리 순서나 부가 파라미터가 달라도 같습니다.
+/// 링크에서 글 번호를 찾지 못하면 `{site}:{link}` 입니다.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Post {
+    pub id: String,
+    /// 게시물 출처 사이트 id (예: `dc`). 같은 id 의 저장 파일에 병합됩니다.
+    pub site: String,
+    /// 게시판 id (예: 디시 갤러리 id, 엠팍 `bullpen`)
+    #[serde(default)]
+    pub board: String,
+    /// 게시판 안의 글 번호
+    #[serde(default)]
+    pub post_no: String,
+    pub title: String,
+    pub link: String,
+    /// 목록에 표시된 작성자. 목록에 없으면 비어 있습니다.
+    #[serde(default)]
+    pub author: String,
+    /// 게시판에 표시된 작성 시각 그대로
+    #[serde(default)]
+    pub datetime: String,
+    /// 정렬과 만료에 쓰는 시각. 작성 시각을 알면 `posted_at`, 모르면 `first_seen_at` 입니다.
+    pub timestamp: i64,
+    /// 게시판에 표시된 작성 시각 (Unix timestamp). 목록에 시각이 없으면 `None`
+    #[serde(default)]
+    pub posted_at: Option<i64>,
+    /// 처음 수집한 시각
+    pub first_seen_at: i64,
+    /// 대표 이미지 주소
+    #[serde(default)]
+    pub images: String,
+    /// 표시 라벨 (예: `디시`)
+    #[serde(default)]
+    pub label: String,
+    /// 목록에서 제목 옆에 표시된 댓글 수
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_count: Option<u32>,
+    /// 말머리 등 게시판이 붙인 분류
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub flags: PostFlags,
+    /// 새 게시물의 상세 페이지에서 읽은 정보. 사이트에 `detail` 이 설정된 경우에만 채워집니다.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<PostDetail>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PostFlags {
+    /// `first_seen_at` 이 8시간 이내면 `true`
+    #[serde(default)]
+    pub new: bool,
+    /// 목록의 이미지 아이콘이나 상세 페이지에서 이미지를 확인했으면 `true`
+    #[serde(default)]
+    pub has_images: bool,
+}
+
+/// 이전 버전이 저장하던 게시물 형식. 불러올 때 `Post` 로 바꿉니다.
+#[derive(Debug, Deserialize, Clone)]
 pub struct List {
     pub timestamp: i64,
     pub title: String,
     pub datetime: String,
     pub link: String,
     pub images: String,
+    /// 표시 라벨
     pub more: String,
     pub new: bool,
+    #[serde(default)]
+    pub posted_at: Option<i64>,
+    /// 이 필드가 없던 파일은 `timestamp` 로 채웁니다.
+    #[serde(default)]
+    pub first_seen_at: i64,
+    #[serde(default)]
+    pub detail: Option<PostDetail>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 저장 파일의 한 항목. 새 형식이 아니면 이전 형식으로 읽습니다.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum StoredPost {
+    Post(Post),
+    Legacy(List),
+}
+
+impl From<List> for Post {
+    /// `site` 와 `id` 는 비어 있으므로 `Scraper::identify` 로 채워야 합니다.
+    fn from(v: List) -> Self {
+        let has_images = !v.images.is_empty();
+        Post {
+            title: v.title,
+            link: v.link,
+            author: v.detail.as_ref().map(|d| d.author.clone()).unwrap_or_default(),
+            datetime: v.datetime,
+            timestamp: v.timestamp,
+            posted_at: v.posted_at,
+            first_seen_at: if v.first_seen_at == 0 { v.timestamp } else { v.first_seen_at },
+            images: v.images,
+            label: v.more,
+            flags: PostFlags { new: v.new, has_images },
+            detail: v.detail,
+            ..Default::default()
+        }
+    }
+}
+
+/// 게시물 상세 페이지의 작성자, 본문, 이미지와 반응 수
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PostDetail {
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub views: Option<u64>,
+    #[serde(default)]
+    pub recommends: Option<u64>,
+    #[serde(default)]
+    pub comments: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Site {
     pub host: String,
     pub url: String,
+    /// 설정되어 있으면 내장 스크래퍼 대신 선택자 기반 범용 파서를 사용합니다.
+    #[serde(default)]
+    pub selectors: Option<SelectorSpec>,
+    /// 수집 주기 (초). 없으면 기본 주기를 사용합니다.
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// 매 주기마다 0 ~ jitter_secs 사이의 무작위 지연을 더합니다.
+    #[serde(default)]
+    pub jitter_secs: u64,
+    /// 수집할 시간대 (서울 시간). 없으면 항상 수집합니다.
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+    /// 사용할 HTTP 클라이언트 프로필 이름. 없으면 스크래퍼의 기본값(`default` / `bot`)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// 켜면 robots.txt 가 금지한 URL 은 가져오지 않고 `Crawl-delay` 를 따릅니다.
+    #[serde(default)]
+    pub respect_robots: bool,
+    /// 페이지 인코딩 이름 (예: `euc-kr`). 없으면 헤더, BOM, `<meta>` 선언으로 판단합니다.
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// 새 게시물의 상세 페이지 수집 설정. 없으면 상세 페이지를 받지 않습니다.
+    #[serde(default)]
+    pub detail: Option<DetailSpec>,
+    /// 여러 페이지 수집 설정. 없으면 첫 페이지만 수집합니다.
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+    /// 게시판에 표시된 시각의 시간대 (IANA 이름, 예: `Asia/Tokyo`). 없으면 `Asia/Seoul`
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// 댓글 수집 설정. 지원하는 스크래퍼(`dc`)에서만 쓸 수 있습니다.
+    #[serde(default)]
+    pub comments: Option<CommentSpec>,
+}
+
+/// 제목이 맞는 게시물의 댓글을 모아 별도 파일에 저장합니다.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CommentSpec {
+    /// 댓글을 저장할 JSON 파일
+    pub json_path: String,
+    /// 제목에 이 문자열 중 하나가 들어간 게시물만 수집합니다.
+    pub titles: Vec<String>,
+    /// 게시물 하나에서 읽을 최대 댓글 페이지 수
+    #[serde(default = "default_comment_pages")]
+    pub max_pages: u32,
+}
+
+fn default_comment_pages() -> u32 {
+    5
+}
+
+/// 댓글 하나. 답글은 `depth` 가 1 이상이고 `parent` 에 원 댓글 번호가 들어갑니다.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comment {
+    pub no: String,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub depth: u32,
+    pub author: String,
+    /// 고정닉 아이디. 유동닉이면 비어 있습니다.
+    #[serde(default)]
+    pub author_id: String,
+    /// 유동닉의 IP 앞자리
+    #[serde(default)]
+    pub ip: String,
+    pub text: String,
+    /// 사이트에 표시된 작성 시각 그대로
+    pub datetime: String,
+    /// 작성 시각 (Unix timestamp). 사이트 시간대로 읽으며, 읽지 못하면 `None`
+    #[serde(default)]
+    pub posted_at: Option<i64>,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// 게시물 `link` 에 딸린 댓글 목록
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommentThread {
+    pub link: String,
+    pub title: String,
+    /// 마지막으로 댓글을 읽은 시각 (Unix timestamp)
+    pub fetched_at: i64,
+    /// 사이트가 알려준 전체 댓글 수
+    pub total: u32,
+    /// 댓글을 읽을 때 목록에 표시돼 있던 댓글 수. 이 값이 바뀐 게시물만 다시 읽습니다.
+    #[serde(default)]
+    pub list_count: Option<u32>,
+    pub comments: Vec<Comment>,
+}
+
+/// 상세 페이지를 CSS 선택자로 기술한 정의. 선택자를 하나도 쓰지 않으면(`{}`) 스크래퍼의 내장 정의를 사용합니다.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DetailSpec {
+    /// 작성자 요소
+    #[serde(default)]
+    pub author: Option<String>,
+    /// 작성자를 텍스트 대신 읽을 속성 (예: `data-nick`)
+    #[serde(default)]
+    pub author_attr: Option<String>,
+    /// 본문 요소
+    #[serde(default)]
+    pub body: Option<String>,
+    /// 이미지 요소. 없으면 본문 안의 `img` 를 사용합니다.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// 이미지 주소를 읽을 속성
+    #[serde(default = "default_image_attr")]
+    pub image_attr: String,
+    /// 조회수 / 추천수 / 댓글수 요소. 텍스트 안의 숫자만 읽습니다.
+    #[serde(default)]
+    pub views: Option<String>,
+    #[serde(default)]
+    pub recommends: Option<String>,
+    #[serde(default)]
+    pub comments: Option<String>,
+}
+
+fn default_image_attr() -> String {
+    "src".to_string()
+}
+
+impl Default for DetailSpec {
+    fn default() -> Self {
+        DetailSpec {
+            author: None,
+            author_attr: None,
+            body: None,
+            image: None,
+            image_attr: default_image_attr(),
+            views: None,
+            recommends: None,
+            comments: None,
+        }
+    }
+}
+
+impl DetailSpec {
+    /// 선택자가 하나도 없으면 내장 정의를 쓰라는 뜻입니다.
+    pub fn is_builtin(&self) -> bool {
+        self.author.is_none()
+            && self.body.is_none()
+            && self.image.is_none()
+            && self.views.is_none()
+            && self.recommends.is_none()
+            && self.comments.is_none()
+    }
+}
+
+/// 목록의 다음 페이지를 찾는 방법과 멈출 조건
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Pagination {
+    /// 첫 페이지를 포함한 최대 페이지 수
+    pub max_pages: u32,
+    /// 페이지 번호를 넣을 쿼리 파라미터 (예: `page`)
+    #[serde(default)]
+    pub page_param: Option<String>,
+    /// 다음 페이지 링크 선택자 (예: `a.page_next`). `page_param` 이 없을 때 사용합니다.
+    #[serde(default)]
+    pub next_selector: Option<String>,
+    /// 한 페이지의 게시물이 모두 이미 저장된 것이면 멈춥니다.
+    #[serde(default)]
+    pub stop_when_seen: bool,
+}
+
+/// `"HH:MM"` 형식의 시작/종료 시각. 종료가 시작보다 이르면 자정을 넘기는 구간입니다.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ActiveHours {
+    pub start: String,
+    pub end: String,
+}
+
+/// 게시판 목록을 CSS 선택자로 기술한 정의
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SelectorSpec {
+    /// 게시물 한 줄(행)을 감싸는 요소
+    pub row: String,
+    /// 행 안에서 제목/링크를 가진 요소
+    pub title: String,
+    /// 제목 요소에서 링크를 읽을 속성
+    #[serde(default = "default_link_attr")]
+    pub link_attr: String,
+    /// 행 안에서 작성일 요소
+    #[serde(default)]
+    pub date: Option<String>,
+    /// 작성일을 텍스트 대신 읽을 속성 (예: `title`)
+    #[serde(default)]
+    pub date_attr: Option<String>,
+    /// 작성일 형식 (chrono `strftime`). 없으면 흔한 표기를 추측합니다. 사이트의 `timezone` 시각으로 읽습니다.
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// 행 안에서 작성자 요소. 닉네임 필터에 사용됩니다.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// 작성자를 텍스트 대신 읽을 속성 (예: `data-nick`)
+    #[serde(default)]
+    pub author_attr: Option<String>,
+    /// 제목 요소 안에서 댓글 수 요소. 제목에서 빼고 `Post.comment_count` 로 읽습니다.
+    #[serde(default)]
+    pub comment_count: Option<String>,
+    /// 게시물의 표시 라벨 (`Post.label`)
+    pub label: String,
+    /// 게시물의 사이트 id. 다른 사이트의 저장 파일을 함께 쓸 때 지정합니다. 없으면 `host`
+    #[serde(default)]
+    pub site: Option<String>,
+    /// 게시물 링크에 남길 쿼리 파라미터 (예: `["id", "no"]`). 없으면 추적용(`utm_*` 등)만 지웁니다.
+    #[serde(default)]
+    pub link_params: Option<Vec<String>>,
+    #[serde(default)]
+    pub fetch_mode: FetchMode,
+}
+
+fn default_link_attr() -> String {
+    "href".to_string()
+}
+
+/// 목록 페이지를 가져올 때 사용할 HTTP 클라이언트 종류
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchMode {
+    /// 앱 User-Agent를 사용하는 기본 클라이언트
+    #[default]
+    Default,
+    /// 브라우저 User-Agent를 사용하는 봇 클라이언트
+    Bot,
 }
 
+/// 한 사이트 id 의 게시물을 모아 두는 저장소. `json_path` 와 `sqlite_path` 중 하나를 씁니다.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Save {
     pub host: String,
+    /// 최근 72시간의 게시물을 담는 JSON 파일
+    #[serde(default)]
     pub json_path: String,
+    /// 게시물을 `posts` 테이블에 쌓는 SQLite 데이터베이스 파일
+    #[serde(default)]
+    pub sqlite_path: Option<String>,
+    /// SQLite 에 게시물을 보관할 기간 (일). 없으면 지우지 않습니다.
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// JSON 파일을 덮어쓸 때 남길 이전 버전 수 (`{json_path}.1` 이 가장 최근)
+    #[serde(default)]
+    pub backups: u32,
+}
+
+impl Save {
+    /// 저장에 쓰는 파일 경로 (SQLite 가 설정되어 있으면 그 경로)
+    pub fn path(&self) -> &str {
+        self.sqlite_path.as_deref().unwrap_or(&self.json_path)
+    }
+}
+
+/// 저장된 게시물을 모아 쓰는 피드 파일. 수집 주기가 끝날 때마다 다시 만듭니다.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Feed {
+    /// 피드 파일 경로
+    pub path: String,
+    #[serde(default)]
+    pub format: FeedFormat,
+    /// 피드 제목. 없으면 `textminer`
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 피드가 가리키는 주소. 없으면 첫 번째 저장 항목에 게시물을 보내는 사이트의 주소
+    #[serde(default)]
+    pub link: Option<String>,
+    /// 넣을 저장 항목의 host. 비어 있으면 모든 저장 항목
+    #[serde(default)]
+    pub saves: Vec<String>,
+    /// 최신순으로 넣을 최대 게시물 수
+    #[serde(default = "default_feed_items")]
+    pub max_items: usize,
+}
+
+fn default_feed_items() -> usize {
+    50
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// RSS 2.0
+    #[default]
+    Rss,
+    /// Atom 1.0
+    Atom,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Down {
     pub host: String,
     pub title: String,
     pub path: String,
     #[serde(default)]
     pub use_webdriver: bool,
+    /// 게시물과 이미지를 받을 때 사용할 HTTP 클라이언트 프로필 이름. 없으면 사이트의 프로필
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// 이름으로 참조하는 HTTP 클라이언트 설정
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ClientProfile {
+    pub name: String,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// 모든 요청에 붙일 기본 헤더
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 처음부터 보낼 쿠키 (이름 -> 값)
+    #[serde(default)]
+    pub cookies: BTreeMap<String, String>,
+    /// 응답의 Set-Cookie 를 저장해 이후 요청에 보냅니다.
+    #[serde(default)]
+    pub cookie_store: bool,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// `http://`, `https://`, `socks5://`, `socks5h://` 프록시 주소
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// 따라갈 최대 리다이렉트 수. 0 이면 따라가지 않습니다. (기본 10)
+    #[serde(default)]
+    pub max_redirects: Option<usize>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+/// 호스트별 요청 속도 제한. `host` 는 그 하위 도메인에도 적용되며, `"*"` 는 나머지 모든 호스트에 적용됩니다.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub host: String,
+    /// 초당 요청 수 (예: 0.5 는 2초에 한 번)
+    pub requests_per_sec: f64,
+    /// 한 번에 몰아서 보낼 수 있는 요청 수
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// 동시에 진행할 수 있는 최대 요청 수
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+fn default_max_in_flight() -> usize {
+    2
+}
+
+/// 텍스트 응답을 디스크에 저장해 두고 TTL 동안 다시 요청하지 않는 캐시 (개발용)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseCache {
+    pub dir: String,
+    #[serde(default = "default_cache_ttl")]
+    pub ttl_secs: u64,
+}
+
+fn default_cache_ttl() -> u64 {
+    3600
+}
+
+/// 실패한 요청의 재시도 설정. 지연은 시도마다 두 배로 늘어나며 무작위 지터가 더해집니다.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 2,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,11 +531,13 @@ pub struct Images {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Nick {
     pub nick: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     #[serde(default)]
     pub sites: Vec<Site>,
@@ -57,5 +548,100 @@ pub struct Config {
     #[serde(default)]
     pub nicks: Vec<Nick>,
     #[serde(default)]
+    pub profiles: Vec<ClientProfile>,
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimit>,
+    /// 개발용 디스크 응답 캐시. 없으면 사용하지 않습니다.
+    #[serde(default)]
+    pub response_cache: Option<ResponseCache>,
+    /// 저장된 게시물로 만드는 RSS / Atom 피드
+    #[serde(default)]
+    pub feeds: Vec<Feed>,
+    #[serde(default)]
     pub enable_download: bool,
+    /// 이미지 다운로드에 사용할 WebDriver 주소
+    #[serde(default = "default_webdriver_url")]
+    pub webdriver_url: String,
+}
+
+fn default_webdriver_url() -> String {
+    crate::foxfox::DEFAULT_WEBDRIVER_URL.to_string()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            sites: vec![],
+            saves: vec![],
+            downs: vec![],
+            nicks: vec![],
+            profiles: vec![],
+            rate_limits: vec![],
+            response_cache: None,
+            feeds: vec![],
+            enable_download: false,
+            webdriver_url: default_webdriver_url(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 이전 버전이 쓰던 `dc_posts.json` 의 항목 (`first_seen_at` 이 없던 때와 있던 때)
+    const LEGACY_FILE: &str = r#"[
+        {"timestamp":1729213200,"title":"첫 글","datetime":"10.18","link":"https://gall.dcinside.com/board/view/?id=baseball_new11&no=12345&page=1","images":"https://dcimg.example/1.jpg","more":"디시","new":true},
+        {"timestamp":1729216800,"title":"둘째 글","datetime":"11:00","link":"https://gall.dcinside.com/board/view/?id=baseball_new11&no=12346","images":"","more":"디시","new":false,"posted_at":1729216800,"first_seen_at":1729217000}
+    ]"#;
+
+    fn load_legacy() -> Vec<Post> {
+        let entries: Vec<StoredPost> = serde_json::from_str(LEGACY_FILE).unwrap();
+        entries
+            .into_iter()
+            .map(|x| match x {
+                StoredPost::Legacy(v) => Post::from(v),
+                StoredPost::Post(_) => panic!("legacy entry read as Post"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn legacy_entries_become_posts() {
+        let posts = load_legacy();
+        assert_eq!(posts.len(), 2);
+
+        assert_eq!(posts[0].label, "디시");
+        assert!(posts[0].flags.new);
+        assert!(posts[0].flags.has_images);
+        assert_eq!(posts[0].first_seen_at, 1729213200);
+        assert_eq!(posts[0].posted_at, None);
+
+        assert!(!posts[1].flags.new);
+        assert!(!posts[1].flags.has_images);
+        assert_eq!(posts[1].first_seen_at, 1729217000);
+        assert_eq!(posts[1].posted_at, Some(1729216800));
+    }
+
+    #[test]
+    fn new_format_is_not_legacy() {
+        let post = Post {
+            id: "dc:baseball_new11:12345".to_string(),
+            site: "dc".to_string(),
+            title: "글".to_string(),
+            link: "https://gall.dcinside.com/board/view/?id=baseball_new11&no=12345".to_string(),
+            timestamp: 1729213200,
+            first_seen_at: 1729213200,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&vec![post]).unwrap();
+        let entries: Vec<StoredPost> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(&entries[0], StoredPost::Post(v) if v.id == "dc:baseball_new11:12345"));
+    }
+
+    #[test]
+    fn unknown_entry_fails_whole_file() {
+        let json = r#"[{"timestamp":1,"title":"t"}]"#;
+        assert!(serde_json::from_str::<Vec<StoredPost>>(json).is_err());
+    }
 }
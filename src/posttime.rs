@@ -0,0 +1,221 @@
+use std::str::FromStr;
+use chrono::{Datelike, DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use crate::models::Site;
+
+// 사이트에 timezone 이 없을 때 게시판 시각을 읽는 시간대
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Seoul;
+
+// 날짜와 시각이 모두 있는 형식. chrono 의 `%Y` 는 두 자리 연도도 받으므로 `%y` 형식을 먼저 봅니다.
+const DATETIME_FORMATS: [&str; 6] = [
+    "%y.%m.%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y.%m.%d %H:%M:%S",
+    "%Y.%m.%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+];
+// 날짜만 있는 형식 (그날 0시로 봅니다)
+const DATE_FORMATS: [&str; 4] = ["%y.%m.%d", "%Y-%m-%d", "%Y.%m.%d", "%Y/%m/%d"];
+// 이보다 이른 연도는 두 자리 연도를 `%Y` 로 잘못 읽은 것으로 봅니다.
+const MIN_YEAR: i32 = 1000;
+// 연도 없이 월/일만 있는 형식의 구분자 (올해로 봅니다)
+const MONTH_DAY_SEPARATORS: [char; 3] = ['-', '.', '/'];
+// 시각만 있는 형식 (오늘로 봅니다)
+const TIME_FORMATS: [&str; 2] = ["%H:%M:%S", "%H:%M"];
+
+/// 사이트의 `timezone` (IANA 이름). 없거나 잘못된 이름이면 서울입니다.
+pub fn site_timezone(site: &Site) -> Tz {
+    site.timezone
+        .as_deref()
+        .and_then(|name| Tz::from_str(name).ok())
+        .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// 시간대 이름이 올바른지 확인합니다.
+pub fn check_timezone(name: &str) -> Result<(), String> {
+    Tz::from_str(name).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// 사이트 시간대의 현재 시각
+pub fn now_in(tz: Tz) -> DateTime<Tz> {
+    Utc::now().with_timezone(&tz)
+}
+
+/// 사이트 현지의 날짜/시각을 시간대에 맞춰 바꿉니다.
+///
+/// 서머타임으로 겹치는 시각이면 이른 쪽을, 건너뛴 시각이면 한 시간 뒤를 씁니다.
+pub fn localize<Z: TimeZone>(naive: NaiveDateTime, tz: &Z) -> Option<DateTime<Z>> {
+    naive
+        .and_local_timezone(tz.clone())
+        .earliest()
+        .or_else(|| (naive + Duration::hours(1)).and_local_timezone(tz.clone()).earliest())
+}
+
+/// `"5분 전"`, `"3 시간 전"`, `"방금"` 같은 상대 시각. 표현할 수 없을 만큼 먼 값은 `None` 입니다.
+fn parse_relative<Z: TimeZone>(text: &str, now: &DateTime<Z>) -> Option<DateTime<Z>> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.starts_with("방금") {
+        return Some(now.clone());
+    }
+    let rest = compact.strip_suffix("전")?;
+    let split = rest.find(|c: char| !c.is_ascii_digit())?;
+    let (num, unit) = rest.split_at(split);
+    let num: i64 = num.parse().ok()?;
+    let delta = match unit {
+        "초" => TimeDelta::try_seconds(num),
+        "분" => TimeDelta::try_minutes(num),
+        "시간" => TimeDelta::try_hours(num),
+        "일" => TimeDelta::try_days(num),
+        _ => return None,
+    }?;
+    now.clone().checked_sub_signed(delta)
+}
+
+/// 게시판에 표시된 작성 시각을 `now` 의 시간대 기준으로 해석합니다.
+///
+/// 전체 날짜/시각, 날짜만, `MM-DD`(시각이 붙어도 됨), `HH:MM`(오늘, 아직 오지 않은 시각이면 어제),
+/// `어제 HH:MM`, `N분 전` 같은 상대 표기를 받습니다. 해석할 수 없으면 `None` 입니다.
+pub fn parse<Z: TimeZone>(text: &str, now: &DateTime<Z>) -> Option<DateTime<Z>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Some(v) = parse_relative(text, now) {
+        return Some(v);
+    }
+
+    let tz = now.timezone();
+    let today = now.naive_local().date();
+    for fmt in DATETIME_FORMATS {
+        if let Ok(v) = NaiveDateTime::parse_from_str(text, fmt)
+            && v.year() >= MIN_YEAR
+        {
+            return localize(v, &tz);
+        }
+    }
+    for fmt in DATE_FORMATS {
+        if let Ok(v) = NaiveDate::parse_from_str(text, fmt)
+            && v.year() >= MIN_YEAR
+        {
+            return localize(v.and_time(NaiveTime::MIN), &tz);
+        }
+    }
+
+    let (day, time_text) = match text.strip_prefix("어제") {
+        Some(rest) => (today.pred_opt()?, rest.trim()),
+        None => (today, text),
+    };
+    for fmt in TIME_FORMATS {
+        if let Ok(t) = NaiveTime::parse_from_str(time_text, fmt) {
+            let v = localize(day.and_time(t), &tz)?;
+            // 자정 직후에 본 "23:58" 은 어제 글입니다.
+            if v > now.clone() + Duration::minutes(1) {
+                return localize((day.pred_opt()?).and_time(t), &tz);
+            }
+            return Some(v);
+        }
+    }
+    if time_text.is_empty() && day != today {
+        return localize(day.and_time(NaiveTime::MIN), &tz);
+    }
+
+    // 연도 없는 월/일 (`MM.DD HH:MM:SS` 처럼 시각이 붙기도 합니다). 미래가 되면 작년 글입니다.
+    let (date_text, time) = match text.split_once(char::is_whitespace) {
+        Some((d, t)) => {
+            let t = t.trim();
+            let time = TIME_FORMATS.iter().find_map(|fmt| NaiveTime::parse_from_str(t, fmt).ok())?;
+            (d, time)
+        }
+        None => (text, NaiveTime::MIN),
+    };
+    let parts: Vec<&str> = date_text.split(MONTH_DAY_SEPARATORS).collect();
+    if let [m, d] = parts.as_slice() {
+        let (m, d) = (m.trim().parse().ok()?, d.trim().parse().ok()?);
+        let v = localize(NaiveDate::from_ymd_opt(today.year(), m, d)?.and_time(time), &tz)?;
+        if v > now.clone() + Duration::minutes(1) {
+            return localize(NaiveDate::from_ymd_opt(today.year() - 1, m, d)?.and_time(time), &tz);
+        }
+        return Some(v);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Asia::Seoul;
+
+    fn kst(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Tz> {
+        Seoul.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn time_after_now_is_yesterday() {
+        let now = kst(2024, 10, 18, 0, 1);
+        assert_eq!(parse("23:58", &now), Some(kst(2024, 10, 17, 23, 58)));
+        assert_eq!(parse("00:01", &now), Some(now));
+    }
+
+    #[test]
+    fn midnight_is_today() {
+        assert_eq!(parse("00:00", &kst(2024, 10, 18, 0, 1)), Some(kst(2024, 10, 18, 0, 0)));
+        assert_eq!(parse("00:00", &kst(2024, 10, 18, 0, 0)), Some(kst(2024, 10, 18, 0, 0)));
+        assert_eq!(parse("00:00", &kst(2024, 10, 18, 23, 59)), Some(kst(2024, 10, 18, 0, 0)));
+    }
+
+    #[test]
+    fn yesterday_across_midnight() {
+        assert_eq!(parse("어제 23:10", &kst(2024, 10, 18, 0, 5)), Some(kst(2024, 10, 17, 23, 10)));
+        assert_eq!(parse("어제 23:10", &kst(2025, 1, 1, 0, 5)), Some(kst(2024, 12, 31, 23, 10)));
+        assert_eq!(parse("어제 23:10", &kst(2024, 3, 1, 0, 5)), Some(kst(2024, 2, 29, 23, 10)));
+    }
+
+    #[test]
+    fn localize_kst_is_nine_hours_before_utc() {
+        let naive = NaiveDate::from_ymd_opt(2024, 10, 18).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let seoul = localize(naive, &Seoul).unwrap();
+        let utc = localize(naive, &Utc).unwrap();
+        assert_eq!(utc.timestamp() - seoul.timestamp(), 9 * 3600);
+        assert_eq!(seoul.naive_local(), naive);
+        assert_eq!(seoul.with_timezone(&Utc).naive_utc(), naive - Duration::hours(9));
+    }
+
+    #[test]
+    fn month_day_across_new_year() {
+        let now = kst(2025, 1, 1, 0, 30);
+        assert_eq!(parse("12-31", &now), Some(kst(2024, 12, 31, 0, 0)));
+        assert_eq!(parse("12.31", &now), Some(kst(2024, 12, 31, 0, 0)));
+        assert_eq!(parse("01-01", &now), Some(kst(2025, 1, 1, 0, 0)));
+        assert_eq!(parse("01/02", &now), Some(kst(2024, 1, 2, 0, 0)));
+    }
+
+    #[test]
+    fn month_day_with_time() {
+        let now = kst(2024, 10, 18, 12, 5);
+        assert_eq!(
+            parse("10.18 12:01:33", &now),
+            Some(Seoul.with_ymd_and_hms(2024, 10, 18, 12, 1, 33).unwrap())
+        );
+        assert_eq!(parse("10.18 12:30", &now), Some(kst(2023, 10, 18, 12, 30)));
+        assert_eq!(parse("12.31 23:59", &kst(2025, 1, 1, 0, 1)), Some(kst(2024, 12, 31, 23, 59)));
+        assert_eq!(parse("10.18 25:00", &now), None);
+    }
+
+    #[test]
+    fn two_digit_year() {
+        let now = kst(2024, 10, 18, 12, 0);
+        assert_eq!(parse("24.05.01", &now), Some(kst(2024, 5, 1, 0, 0)));
+        assert_eq!(parse("24.05.01 13:20", &now), Some(kst(2024, 5, 1, 13, 20)));
+        assert_eq!(parse("2024.05.01 13:20", &now), Some(kst(2024, 5, 1, 13, 20)));
+        assert_eq!(parse("2024-05-01", &now), Some(kst(2024, 5, 1, 0, 0)));
+        assert_eq!(parse("24-05-01", &now), None);
+    }
+
+    #[test]
+    fn relative_overflow_is_none() {
+        let now = kst(2024, 10, 18, 12, 0);
+        assert_eq!(parse("100000000일 전", &now), None);
+        assert_eq!(parse(&format!("{}초 전", i64::MAX), &now), None);
+    }
+}
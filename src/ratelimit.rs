@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+use crate::models::{Config, RateLimit};
+
+// 설정에 맞는 규칙이 없는 호스트에 적용되는 기본값
+const DEFAULT_REQUESTS_PER_SEC: f64 = 2.0;
+const DEFAULT_BURST: u32 = 2;
+const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+// 모든 호스트에 적용되는 규칙의 host 값
+pub const WILDCARD_HOST: &str = "*";
+
+/// 호스트 하나의 토큰 버킷과 동시 요청 제한
+struct HostState {
+    bucket: Mutex<Bucket>,
+    in_flight: Arc<Semaphore>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+    rate: f64,
+    burst: f64,
+}
+
+impl Bucket {
+    /// 토큰 하나를 예약하고, 토큰이 생길 때까지 기다려야 할 시간을 돌려줍니다.
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// 요청이 끝날 때까지 들고 있어야 하는 동시 요청 허가
+pub struct Permit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+struct Limiter {
+    rules: Vec<RateLimit>,
+    hosts: Mutex<HashMap<String, Arc<HostState>>>,
+}
+
+lazy_static! {
+    static ref LIMITER: RwLock<Arc<Limiter>> = RwLock::new(Arc::new(Limiter::new(vec![])));
+    // robots.txt 의 Crawl-delay (호스트 → 초). 설정을 다시 읽어도 유지됩니다.
+    static ref CRAWL_DELAYS: Mutex<HashMap<String, f64>> = Mutex::new(HashMap::new());
+}
+
+/// 규칙의 속도와 Crawl-delay 중 더 느린 쪽
+fn effective_rate(rate: f64, host: &str) -> f64 {
+    match CRAWL_DELAYS.lock().unwrap_or_else(|e| e.into_inner()).get(host) {
+        Some(delay) => rate.min(1.0 / delay),
+        None => rate,
+    }
+}
+
+/// `host` 가 규칙의 host 와 같거나 그 하위 도메인인지 확인합니다.
+fn host_matches(rule: &str, host: &str) -> bool {
+    host == rule || host.strip_suffix(rule).is_some_and(|rest| rest.ends_with('.'))
+}
+
+impl Limiter {
+    fn new(rules: Vec<RateLimit>) -> Self {
+        Limiter {
+            rules,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 호스트에 맞는 규칙과 버킷 키. 구체적인 규칙은 하위 도메인끼리 버킷을 공유합니다.
+    fn rule_for(&self, host: &str) -> (String, RateLimit) {
+        let specific = self
+            .rules
+            .iter()
+            .filter(|r| r.host != WILDCARD_HOST && host_matches(&r.host, host))
+            .max_by_key(|r| r.host.len());
+        if let Some(rule) = specific {
+            return (rule.host.clone(), rule.clone());
+        }
+        let fallback = self
+            .rules
+            .iter()
+            .find(|r| r.host == WILDCARD_HOST)
+            .cloned()
+            .unwrap_or_else(|| RateLimit {
+                host: WILDCARD_HOST.to_string(),
+                requests_per_sec: DEFAULT_REQUESTS_PER_SEC,
+                burst: DEFAULT_BURST,
+                max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            });
+        (host.to_string(), fallback)
+    }
+
+    fn state(&self, host: &str) -> Arc<HostState> {
+        let (key, rule) = self.rule_for(host);
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(hosts.entry(key).or_insert_with(|| {
+            let rate = effective_rate(rule.requests_per_sec, host);
+            // Crawl-delay 가 걸린 호스트는 몰아서 보내지 않습니다.
+            let burst = if rate < rule.requests_per_sec { 1.0 } else { rule.burst.max(1) as f64 };
+            Arc::new(HostState {
+                bucket: Mutex::new(Bucket {
+                    tokens: burst,
+                    last: Instant::now(),
+                    rate,
+                    burst,
+                }),
+                in_flight: Arc::new(Semaphore::new(rule.max_in_flight.max(1))),
+            })
+        }))
+    }
+}
+
+/// 설정의 규칙으로 제한기를 교체합니다. 진행 중인 요청은 이전 제한기의 허가를 그대로 씁니다.
+pub fn install(config: &Config) {
+    *LIMITER.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(Limiter::new(config.rate_limits.clone()));
+}
+
+/// robots.txt 의 Crawl-delay 를 호스트의 속도 제한에 반영합니다. 규칙보다 느릴 때만 적용됩니다.
+pub fn set_crawl_delay(host: &str, delay: Duration) {
+    let secs = delay.as_secs_f64();
+    if secs <= 0.0 {
+        return;
+    }
+    let prev = CRAWL_DELAYS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(host.to_string(), secs);
+    if prev == Some(secs) {
+        return;
+    }
+    let limiter = Arc::clone(&LIMITER.read().unwrap_or_else(|e| e.into_inner()));
+    let state = limiter.state(host);
+    let mut bucket = state.bucket.lock().unwrap_or_else(|e| e.into_inner());
+    if 1.0 / secs < bucket.rate {
+        bucket.rate = 1.0 / secs;
+        bucket.burst = 1.0;
+        bucket.tokens = bucket.tokens.min(1.0);
+        log::info!("rate limit: crawl-delay {:?} for {}", delay, host);
+    }
+}
+
+/// URL 의 호스트에 대해 동시 요청 허가와 토큰을 얻을 때까지 기다립니다.
+pub async fn acquire(url: &str) -> Permit {
+    let host = match Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) {
+        Some(v) => v,
+        None => return Permit { _permit: None },
+    };
+    let limiter = Arc::clone(&LIMITER.read().unwrap_or_else(|e| e.into_inner()));
+    let state = limiter.state(&host);
+    let permit = Arc::clone(&state.in_flight).acquire_owned().await.ok();
+    let wait = state.bucket.lock().unwrap_or_else(|e| e.into_inner()).reserve();
+    if !wait.is_zero() {
+        log::debug!("rate limit: waiting {:?} for {}", wait, host);
+        tokio::time::sleep(wait).await;
+    }
+    Permit { _permit: permit }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use url::Url;
+use crate::utils::{self, FetchError};
+use crate::{clients, ratelimit};
+
+// robots.txt 를 다시 받기 전까지 캐시해 두는 시간
+const CACHE_TTL_SECS: u64 = 86400;
+// 서버 오류 등으로 받지 못했을 때(전체 금지로 취급) 다시 시도하기 전까지의 시간
+const UNREACHABLE_TTL_SECS: u64 = 600;
+
+/// robots.txt 의 `User-agent` 그룹 하나
+#[derive(Debug, Default)]
+struct Group {
+    agents: Vec<String>,
+    /// (허용 여부, 경로 패턴)
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<f64>,
+}
+
+/// 호스트 하나의 robots.txt 해석 결과
+#[derive(Debug)]
+enum Robots {
+    /// 파일이 없음 (4xx) → 전부 허용
+    AllowAll,
+    /// 받지 못함 (5xx, 연결 실패) → 전부 금지
+    DisallowAll,
+    Groups(Vec<Group>),
+}
+
+struct Entry {
+    robots: Arc<Robots>,
+    expires: Instant,
+}
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, Entry>> = Mutex::new(HashMap::new());
+    // 같은 호스트의 robots.txt 를 동시에 여러 번 받지 않도록 잡는 origin 별 잠금
+    static ref FETCH_LOCKS: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// origin 별 잠금. 응답이 없는 호스트가 다른 호스트의 확인을 막지 않습니다.
+fn fetch_lock(origin: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = FETCH_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    Arc::clone(locks.entry(origin.to_string()).or_default())
+}
+
+/// robots.txt 본문을 그룹 목록으로 해석합니다.
+fn parse(body: &str) -> Vec<Group> {
+    let mut groups: Vec<Group> = vec![];
+    // 마지막 그룹에 아직 규칙이 없으면 이어지는 User-agent 는 같은 그룹입니다.
+    let mut open = false;
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let (key, value) = match line.split_once(':') {
+            Some((k, v)) => (k.trim().to_ascii_lowercase(), v.trim()),
+            None => continue,
+        };
+        match key.as_str() {
+            "user-agent" => {
+                if !open {
+                    groups.push(Group::default());
+                    open = true;
+                }
+                if let Some(group) = groups.last_mut() {
+                    group.agents.push(value.to_ascii_lowercase());
+                }
+            }
+            "allow" | "disallow" => {
+                open = false;
+                // 값이 빈 Disallow 는 아무것도 막지 않습니다.
+                if let Some(group) = groups.last_mut()
+                    && !value.is_empty()
+                {
+                    group.rules.push((key == "allow", value.to_string()));
+                }
+            }
+            "crawl-delay" => {
+                open = false;
+                if let Some(group) = groups.last_mut() {
+                    group.crawl_delay = value.parse::<f64>().ok().filter(|v| v.is_finite() && *v > 0.0);
+                }
+            }
+            _ => {}
+        }
+    }
+    groups
+}
+
+/// `*` 와 끝의 `$` 를 지원하는 경로 패턴 비교
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut rest = match path.strip_prefix(parts[0]) {
+        Some(v) => v,
+        None => return false,
+    };
+    let last = parts.len() - 1;
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        if i == last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+impl Robots {
+    /// 사용자 에이전트에 적용되는 그룹들. 이름이 맞는 그룹이 없으면 `*` 그룹을 씁니다.
+    fn groups_for(&self, user_agent: &str) -> Vec<&Group> {
+        let groups = match self {
+            Robots::Groups(v) => v,
+            _ => return vec![],
+        };
+        let ua = user_agent.to_ascii_lowercase();
+        let specific: Vec<&Group> = groups
+            .iter()
+            .filter(|g| g.agents.iter().any(|a| a != "*" && ua.contains(a.as_str())))
+            .collect();
+        if !specific.is_empty() {
+            return specific;
+        }
+        groups.iter().filter(|g| g.agents.iter().any(|a| a == "*")).collect()
+    }
+
+    /// 가장 길게 맞는 규칙을 따르고, 길이가 같으면 Allow 가 우선합니다.
+    fn allows(&self, user_agent: &str, path: &str) -> bool {
+        match self {
+            Robots::AllowAll => return true,
+            Robots::DisallowAll => return false,
+            Robots::Groups(_) => {}
+        }
+        if path == "/robots.txt" {
+            return true;
+        }
+        self.groups_for(user_agent)
+            .iter()
+            .flat_map(|g| g.rules.iter())
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+
+    fn crawl_delay(&self, user_agent: &str) -> Option<f64> {
+        self.groups_for(user_agent)
+            .iter()
+            .filter_map(|g| g.crawl_delay)
+            .reduce(f64::max)
+    }
+}
+
+/// 캐시에서 호스트의 robots.txt 를 찾고, 없거나 만료되었으면 새로 받습니다.
+async fn load(origin: &str, profile: &str) -> Arc<Robots> {
+    let cached = |origin: &str| {
+        let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .get(origin)
+            .filter(|e| e.expires > Instant::now())
+            .map(|e| Arc::clone(&e.robots))
+    };
+    if let Some(robots) = cached(origin) {
+        return robots;
+    }
+    let lock = fetch_lock(origin);
+    let _guard = lock.lock().await;
+    if let Some(robots) = cached(origin) {
+        return robots;
+    }
+
+    let url = format!("{}/robots.txt", origin);
+    let (robots, ttl) = match utils::get_text_response(&url, profile, None).await {
+        Ok(body) => {
+            info!("robots.txt loaded for {}", origin);
+            (Robots::Groups(parse(&body)), CACHE_TTL_SECS)
+        }
+        Err(FetchError::Status { status, .. }) if status.is_client_error() => {
+            info!("no robots.txt for {} ({}), allowing all", origin, status);
+            (Robots::AllowAll, CACHE_TTL_SECS)
+        }
+        Err(e) => {
+            warn!("robots.txt unavailable, treating {} as disallowed: {}", origin, e);
+            (Robots::DisallowAll, UNREACHABLE_TTL_SECS)
+        }
+    };
+    let robots = Arc::new(robots);
+    CACHE.lock().unwrap_or_else(|e| e.into_inner()).insert(
+        origin.to_string(),
+        Entry {
+            robots: Arc::clone(&robots),
+            expires: Instant::now() + Duration::from_secs(ttl),
+        },
+    );
+    robots
+}
+
+/// 프로필의 사용자 에이전트로 URL 을 가져와도 되는지 확인합니다.
+///
+/// 금지된 URL 은 로그에 남기고, robots.txt 의 `Crawl-delay` 는 속도 제한에 반영합니다.
+pub async fn allowed(url: &str, profile: &str) -> bool {
+    let parsed = match Url::parse(url) {
+        Ok(v) => v,
+        Err(_) => return true,
+    };
+    let host = match parsed.host_str() {
+        Some(v) => v.to_string(),
+        None => return true,
+    };
+    let origin = parsed.origin().ascii_serialization();
+    let robots = load(&origin, profile).await;
+    let user_agent = clients::get(profile).user_agent;
+
+    if let Some(delay) = robots.crawl_delay(&user_agent) {
+        ratelimit::set_crawl_delay(&host, Duration::from_secs_f64(delay));
+    }
+    let path = match parsed.query() {
+        Some(q) => format!("{}?{}", parsed.path(), q),
+        None => parsed.path().to_string(),
+    };
+    let ok = robots.allows(&user_agent, &path);
+    if !ok {
+        warn!("robots.txt disallows {} for {:?}, skipped", url, user_agent);
+    }
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UA: &str = "Mozilla/5.0 textminer-bot/1.0";
+
+    fn robots(body: &str) -> Robots {
+        Robots::Groups(parse(body))
+    }
+
+    #[test]
+    fn parses_groups() {
+        let groups = parse(
+            "# comment\nUser-agent: a\nUser-agent: B\nDisallow: /x # tail\nCrawl-delay: 2.5\n\nUser-agent: *\nDisallow:\nAllow: /y\n",
+        );
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].agents, ["a", "b"]);
+        assert_eq!(groups[0].rules, [(false, "/x".to_string())]);
+        assert_eq!(groups[0].crawl_delay, Some(2.5));
+        assert_eq!(groups[1].agents, ["*"]);
+        assert_eq!(groups[1].rules, [(true, "/y".to_string())]);
+        assert_eq!(groups[1].crawl_delay, None);
+    }
+
+    #[test]
+    fn pattern_prefix_wildcard_and_anchor() {
+        assert!(pattern_matches("/board", "/board/view?id=1"));
+        assert!(!pattern_matches("/board", "/mboard"));
+        assert!(pattern_matches("/*.php", "/mp/b.php?m=view"));
+        assert!(pattern_matches("/a*c*e", "/abcde"));
+        assert!(!pattern_matches("/a*c*e", "/abd"));
+        assert!(pattern_matches("/*.jpg$", "/img/1.jpg"));
+        assert!(!pattern_matches("/*.jpg$", "/img/1.jpg?w=100"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exact/more"));
+    }
+
+    #[test]
+    fn longest_match_wins() {
+        let r = robots("User-agent: *\nDisallow: /board/\nAllow: /board/lists\n");
+        assert!(r.allows(UA, "/board/lists/?id=x"));
+        assert!(!r.allows(UA, "/board/view/?id=x"));
+        assert!(r.allows(UA, "/other"));
+    }
+
+    #[test]
+    fn allow_wins_ties() {
+        let r = robots("User-agent: *\nDisallow: /page\nAllow: /page\n");
+        assert!(r.allows(UA, "/page"));
+    }
+
+    #[test]
+    fn wildcard_rules() {
+        let r = robots("User-agent: *\nDisallow: /*?sort=\nDisallow: /*.gif$\n");
+        assert!(!r.allows(UA, "/list?sort=new"));
+        assert!(r.allows(UA, "/list?page=2"));
+        assert!(!r.allows(UA, "/a/b.gif"));
+        assert!(r.allows(UA, "/a/b.gif?x=1"));
+    }
+
+    #[test]
+    fn specific_agent_group_replaces_star() {
+        let r = robots("User-agent: *\nDisallow: /\n\nUser-agent: textminer-bot\nDisallow: /private\nCrawl-delay: 3\n");
+        assert!(r.allows(UA, "/board"));
+        assert!(!r.allows(UA, "/private/1"));
+        assert!(!r.allows("other-bot", "/board"));
+        assert_eq!(r.crawl_delay(UA), Some(3.0));
+        assert_eq!(r.crawl_delay("other-bot"), None);
+    }
+
+    #[test]
+    fn robots_txt_itself_and_fallbacks() {
+        let r = robots("User-agent: *\nDisallow: /\n");
+        assert!(r.allows(UA, "/robots.txt"));
+        assert!(Robots::AllowAll.allows(UA, "/anything"));
+        assert!(!Robots::DisallowAll.allows(UA, "/anything"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{NaiveTime, Timelike, Utc};
+use chrono_tz::Asia::Seoul;
+use anyhow::{Context, Result};
+use tokio::sync::watch;
+use crate::models::{ActiveHours, Config, Site};
+use crate::utils;
+
+// Scheduling constants
+const DEFAULT_INTERVAL_SECS: u64 = 300; // 5 minutes when a site has no interval_secs
+const STARTUP_STAGGER_MS: u64 = 500; // Delay between the first run of each site
+const ACTIVE_HOURS_FORMAT: &str = "%H:%M";
+
+/// 사이트 하나의 수집 일정
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub interval: Duration,
+    pub jitter: Duration,
+    pub window: Option<(NaiveTime, NaiveTime)>,
+}
+
+fn parse_hours(hours: &ActiveHours) -> Result<(NaiveTime, NaiveTime)> {
+    let start = NaiveTime::parse_from_str(&hours.start, ACTIVE_HOURS_FORMAT)
+        .context(format!("invalid active_hours.start {:?}, expected HH:MM", hours.start))?;
+    let end = NaiveTime::parse_from_str(&hours.end, ACTIVE_HOURS_FORMAT)
+        .context(format!("invalid active_hours.end {:?}, expected HH:MM", hours.end))?;
+    Ok((start, end))
+}
+
+impl Schedule {
+    pub fn from_site(site: &Site) -> Result<Self> {
+        let interval_secs = site.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS);
+        if interval_secs == 0 {
+            anyhow::bail!("interval_secs must be greater than 0");
+        }
+        Ok(Schedule {
+            interval: Duration::from_secs(interval_secs),
+            jitter: Duration::from_secs(site.jitter_secs),
+            window: site.active_hours.as_ref().map(parse_hours).transpose()?,
+        })
+    }
+
+    /// 주어진 시각이 수집 시간대 안인지 확인합니다.
+    pub fn is_active_at(&self, now: NaiveTime) -> bool {
+        match self.window {
+            None => true,
+            Some((start, end)) if start <= end => now >= start && now < end,
+            // 자정을 넘기는 구간 (예: 22:00 ~ 02:00)
+            Some((start, end)) => now >= start || now < end,
+        }
+    }
+
+    /// 다음 실행까지 기다릴 시간 (주기 + 무작위 지연)
+    pub fn next_delay(&self) -> Duration {
+        self.interval + utils::random_upto(self.jitter)
+    }
+}
+
+fn now_seoul() -> NaiveTime {
+    let now = Utc::now().with_timezone(&Seoul);
+    NaiveTime::from_hms_opt(now.hour(), now.minute(), now.second()).unwrap_or_default()
+}
+
+/// 사이트마다 독립된 작업을 띄워 각자의 일정대로 `job` 을 실행합니다.
+///
+/// 한 사이트가 느려도 다른 사이트의 일정에는 영향을 주지 않습니다.
+/// 설정이 바뀌면 추가/삭제/변경된 사이트만 다시 띄우고, 나머지는 다음 실행부터 새 설정을 씁니다.
+pub struct Scheduler<F> {
+    job: F,
+    config: watch::Sender<Arc<Config>>,
+    // 사이트 정의(JSON) → 작업 중지 신호. 보내는 쪽을 버리면 진행 중인 실행을 마친 뒤 멈춥니다.
+    tasks: HashMap<String, watch::Sender<()>>,
+}
+
+fn site_key(site: &Site) -> String {
+    serde_json::to_string(site).unwrap_or_default()
+}
+
+impl<F, Fut> Scheduler<F>
+where
+    F: Fn(Arc<Config>, Site) -> Fut + Send + Sync + Clone + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    pub fn start(config: Arc<Config>, job: F) -> Self {
+        let (sender, _) = watch::channel(Arc::clone(&config));
+        let mut scheduler = Scheduler {
+            job,
+            config: sender,
+            tasks: HashMap::new(),
+        };
+        scheduler.reload(config);
+        scheduler
+    }
+
+    /// 새 설정을 넘기고 바뀐 사이트의 작업만 다시 띄웁니다. 다시 띄운 사이트 수를 돌려줍니다.
+    pub fn reload(&mut self, config: Arc<Config>) -> usize {
+        self.config.send_replace(Arc::clone(&config));
+        let keys: HashSet<String> = config.sites.iter().map(site_key).collect();
+        self.tasks.retain(|key, _| keys.contains(key));
+
+        let mut started = 0;
+        for site in config.sites.iter() {
+            let key = site_key(site);
+            if self.tasks.contains_key(&key) {
+                continue;
+            }
+            let stop = self.spawn(site.clone(), started);
+            self.tasks.insert(key, stop);
+            started += 1;
+        }
+        started
+    }
+
+    fn spawn(&self, site: Site, order: usize) -> watch::Sender<()> {
+        let schedule = Schedule::from_site(&site).unwrap_or_else(|e| {
+            log::warn!("site {}: {:#}, using default schedule", site.host, e);
+            Schedule {
+                interval: Duration::from_secs(DEFAULT_INTERVAL_SECS),
+                jitter: Duration::ZERO,
+                window: None,
+            }
+        });
+        let (stop, mut stopped) = watch::channel(());
+        let config = self.config.subscribe();
+        let job = self.job.clone();
+        tokio::spawn(async move {
+            let mut delay = Duration::from_millis(order as u64 * STARTUP_STAGGER_MS);
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = stopped.changed() => break,
+                }
+                if schedule.is_active_at(now_seoul()) {
+                    let current = Arc::clone(&config.borrow());
+                    if let Err(e) = job(current, site.clone()).await {
+                        log::error!("Scraping {} failed: {:#}", site.host, e);
+                    }
+                } else {
+                    log::info!("{} is outside active hours, skipped", site.host);
+                }
+                delay = schedule.next_delay();
+            }
+            log::info!("{} schedule stopped", site.host);
+        });
+        stop
+    }
+}
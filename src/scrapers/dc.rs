@@ -1,17 +1,68 @@
 use scraper::{Html, Selector};
-use chrono::Utc;
-use chrono_tz::Asia::Seoul;
+use chrono_tz::Tz;
 use url::Url;
 use anyhow::{Result, Context};
-use crate::models::{List, Nick, Images};
+use async_trait::async_trait;
+use crate::posttime;
+use crate::models::{Comment, DetailSpec, Nick, Images, Post, PostFlags, Site};
+use super::{dc_comments, links, title, Scraper, LIST_MAX_AGE_SECS};
 
-// Post age filter constant (24 hours in seconds)
-const MAX_POST_AGE_SECS: i64 = 86400;
+pub struct DcScraper;
 
-pub fn parse_dc(html: &str, site_url: &str, nick_list: &[Nick]) -> (Result<Vec<List>>, Vec<String>) {
-    let mut _list: Vec<List> = vec![];
+#[async_trait]
+impl Scraper for DcScraper {
+    fn host(&self) -> &str {
+        "dc"
+    }
+
+    fn label(&self) -> &str {
+        "디시"
+    }
+
+    fn link_params(&self) -> Option<Vec<&str>> {
+        Some(vec!["id", "no"])
+    }
+
+    fn parse(&self, html: &str, site: &Site, nick_list: &[Nick]) -> Result<Vec<Post>> {
+        let (results, _logs) = parse_dc(html, &site.url, nick_list, posttime::site_timezone(site));
+        results
+    }
+
+    fn parse_images(&self, html: &str, path: &str, title: &str, host: &str) -> Result<Vec<Images>> {
+        parse_dcimage(html, path, title, host)
+    }
+
+    fn supports_comments(&self) -> bool {
+        true
+    }
+
+    async fn collect_comments(
+        &self,
+        post_url: &str,
+        site: &Site,
+        profile: &str,
+        max_pages: u32,
+    ) -> Result<(u32, Vec<Comment>)> {
+        dc_comments::collect(post_url, site, profile, max_pages).await
+    }
+
+    fn detail_spec(&self) -> Option<DetailSpec> {
+        Some(DetailSpec {
+            author: Some("div.gall_writer".to_string()),
+            author_attr: Some("data-nick".to_string()),
+            body: Some("div.write_div".to_string()),
+            views: Some("span.gall_count".to_string()),
+            recommends: Some("span.gall_reply_num".to_string()),
+            comments: Some("span.gall_comment".to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+pub fn parse_dc(html: &str, site_url: &str, nick_list: &[Nick], tz: Tz) -> (Result<Vec<Post>>, Vec<String>) {
+    let mut _list: Vec<Post> = vec![];
     let mut logs = Vec::new();
-    let _today = Utc::now().with_timezone(&Seoul);
+    let _today = posttime::now_in(tz);
     let fragment = Html::parse_fragment(html);
     
     let meta_link_sel_res = Selector::parse(r#"input[id="list_url"]"#);
@@ -19,25 +70,19 @@ pub fn parse_dc(html: &str, site_url: &str, nick_list: &[Nick]) -> (Result<Vec<L
     let title_sel_res = Selector::parse("td.gall_tit > a");
     let date_sel_res = Selector::parse("td.gall_date");
     let nick_sel_res = Selector::parse("td.gall_writer");
+    let subject_sel_res = Selector::parse("td.gall_subject");
+    let image_icon_sel_res = Selector::parse("em.icon_pic, em.icon_recomimg");
+    let reply_sel_res = Selector::parse("td.gall_tit span.reply_num");
 
-    if let (Ok(meta_link_sel), Ok(part_sel), Ok(title_sel), Ok(date_sel), Ok(nick_sel)) = 
-           (meta_link_sel_res, part_sel_res, title_sel_res, date_sel_res, nick_sel_res) {
+    if let (Ok(meta_link_sel), Ok(part_sel), Ok(title_sel), Ok(date_sel), Ok(nick_sel), Ok(subject_sel), Ok(image_icon_sel), Ok(reply_sel)) =
+           (meta_link_sel_res, part_sel_res, title_sel_res, date_sel_res, nick_sel_res, subject_sel_res, image_icon_sel_res, reply_sel_res) {
         
         let _host_val = fragment.select(&meta_link_sel).next()
             .and_then(|v| v.value().attr("value"))
             .unwrap_or_default();
 
-        let host_str = if !_host_val.is_empty() {
-            Url::parse(_host_val).ok()
-                .and_then(|u| u.host_str().map(|h| h.to_string()))
-                .unwrap_or_else(|| Url::parse(site_url).ok()
-                    .and_then(|u| u.host_str().map(|h| h.to_string()))
-                    .unwrap_or_default())
-        } else {
-            Url::parse(site_url).ok()
-                .and_then(|u| u.host_str().map(|h| h.to_string()))
-                .unwrap_or_default()
-        };
+        // 페이지가 알려주는 목록 주소가 있으면 그것을, 없으면 설정의 주소를 기준으로 링크를 풉니다.
+        let base = Url::parse(_host_val).or_else(|_| Url::parse(site_url)).ok();
 
         for element in fragment.select(&part_sel) {
             let td_title = match element.select(&title_sel).next() {
@@ -45,7 +90,6 @@ pub fn parse_dc(html: &str, site_url: &str, nick_list: &[Nick]) -> (Result<Vec<L
                 None => continue,
             };
             
-            let _title_raw = td_title.inner_html();
             let _link = td_title.value().attr("href").unwrap_or_default().to_string();
             
             let td_date = match element.select(&date_sel).next() {
@@ -59,27 +103,39 @@ pub fn parse_dc(html: &str, site_url: &str, nick_list: &[Nick]) -> (Result<Vec<L
                 .and_then(|v| v.value().attr("data-nick"))
                 .unwrap_or_default()
                 .to_string();
+            // 말머리 (예: "일반", "정보")
+            let _tags: Vec<String> = element.select(&subject_sel).next()
+                .map(|v| v.text().collect::<String>().trim().to_string())
+                .filter(|v| !v.is_empty())
+                .into_iter()
+                .collect();
+            let _has_images = td_title.select(&image_icon_sel).next().is_some();
 
-            let _timestamp = chrono::NaiveDateTime::parse_from_str(&_date, "%Y-%m-%d %H:%M:%S");
+            // 게시판의 시각은 사이트 현지 시각이므로 UTC 로 보지 않고 시간대를 거쳐 바꿉니다.
+            let _timestamp = chrono::NaiveDateTime::parse_from_str(&_date, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .and_then(|v| posttime::localize(v, &tz));
 
-            let processed_title = _title_raw.split("</em>")
-                .last()
-                .unwrap_or("")
-                .chars()
-                .filter(|&c| c != '\n' && c != '\t')
-                .collect::<String>();
+            // 아이콘(`<em>`)은 텍스트가 없어 빠지고, 댓글 수는 제목 밖의 `span.reply_num` 에 있습니다.
+            let processed_title = title::normalize_text(&td_title.text().collect::<String>());
+            let _comment_count = element.select(&reply_sel).next()
+                .and_then(|v| title::parse_count(&v.text().collect::<String>()));
 
-            if let Ok(v) = _timestamp {
-                let _diff = _today.timestamp() - v.and_utc().timestamp();
-                if _diff < MAX_POST_AGE_SECS && !nick_list.iter().any(|e| _nick_text == e.nick) {
-                    _list.push(List {
-                        timestamp: _today.timestamp(),
+            if let Some(v) = _timestamp {
+                let _diff = _today.timestamp() - v.timestamp();
+                if _diff < LIST_MAX_AGE_SECS && !nick_list.iter().any(|e| _nick_text == e.nick) {
+                    _list.push(Post {
                         title: processed_title,
+                        comment_count: _comment_count,
+                        link: links::resolve(base.as_ref(), &_link),
+                        author: _nick_text,
                         datetime: _date_text,
-                        link: format!("https://{}{}", host_str, _link),
-                        images: String::new(),
-                        more: "디시".to_string(),
-                        new: true,
+                        timestamp: v.timestamp(),
+                        posted_at: Some(v.timestamp()),
+                        first_seen_at: _today.timestamp(),
+                        tags: _tags,
+                        flags: PostFlags { new: true, has_images: _has_images },
+                        ..Default::default()
                     });
                 }
             } else {
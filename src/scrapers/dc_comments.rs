@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use url::Url;
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use chrono_tz::Tz;
+use crate::models::{Comment, Site};
+use crate::{posttime, robots, utils};
+
+// 댓글 AJAX 주소 (게시물 주소 기준)
+const COMMENT_PATH: &str = "/board/comment/";
+// 자동으로 붙는 "댓글돌이" 항목의 nicktype
+const COMMENT_BOY: &str = "COMMENT_BOY";
+
+/// 문자열이나 숫자로 오는 필드를 문자열로 읽습니다.
+fn field(v: &Value, key: &str) -> String {
+    match v.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// 댓글 응답 JSON 을 (전체 댓글 수, 댓글) 로 읽습니다.
+///
+/// 답글(`depth` > 0)의 `parent` 는 `c_no`, 없으면 바로 앞의 원 댓글 번호입니다.
+/// 연도 없는 작성 시각(`10.18 12:01:33`)은 `now` 의 시간대와 연도로 읽습니다.
+pub fn parse_dc_comments(json: &str, now: &DateTime<Tz>) -> Result<(u32, Vec<Comment>)> {
+    let value: Value = serde_json::from_str(json).context("Invalid comment response")?;
+    let total = field(&value, "total_cnt").parse().unwrap_or(0);
+    let items = match value.get("comments") {
+        Some(Value::Array(v)) => v.as_slice(),
+        Some(Value::Null) | None => &[],
+        Some(_) => anyhow::bail!("comments is not an array"),
+    };
+
+    let mut _list: Vec<Comment> = vec![];
+    let mut last_root: Option<String> = None;
+    for c in items {
+        if field(c, "nicktype") == COMMENT_BOY {
+            continue;
+        }
+        let no = field(c, "no");
+        let depth: u32 = field(c, "depth").parse().unwrap_or(0);
+        let parent = if depth == 0 {
+            last_root = Some(no.clone());
+            None
+        } else {
+            Some(field(c, "c_no"))
+                .filter(|v| !v.is_empty() && v != "0")
+                .or_else(|| last_root.clone())
+        };
+        // 본문에는 디시콘 이미지 등 HTML 이 섞여 있어 텍스트만 남깁니다.
+        let memo = field(c, "memo");
+        let text = Html::parse_fragment(&memo)
+            .root_element()
+            .text()
+            .collect::<String>()
+            .trim()
+            .to_string();
+        _list.push(Comment {
+            no,
+            parent,
+            depth,
+            author: field(c, "name"),
+            author_id: field(c, "user_id"),
+            ip: field(c, "ip"),
+            text,
+            posted_at: posttime::parse(&field(c, "reg_date"), now).map(|v| v.timestamp()),
+            datetime: field(c, "reg_date"),
+            deleted: field(c, "del_yn") == "Y" || !matches!(field(c, "is_delete").as_str(), "" | "0"),
+        });
+    }
+    Ok((total, _list))
+}
+
+/// 게시물 페이지의 댓글 요청 토큰 (`e_s_n_o`)
+fn read_token(html: &str) -> Option<String> {
+    let selector = Selector::parse("input#e_s_n_o").ok()?;
+    Html::parse_document(html)
+        .select(&selector)
+        .next()
+        .and_then(|v| v.value().attr("value"))
+        .map(str::to_string)
+        .filter(|v| !v.is_empty())
+}
+
+/// 게시물 주소로 댓글을 최대 `max_pages` 페이지까지 모읍니다.
+///
+/// `site.respect_robots` 면 댓글 AJAX 주소도 robots.txt 로 확인하고, 막혀 있으면 실패로 돌려줍니다.
+pub async fn collect(post_url: &str, site: &Site, profile: &str, max_pages: u32) -> Result<(u32, Vec<Comment>)> {
+    let url = Url::parse(post_url).context(format!("Invalid post URL: {}", post_url))?;
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let id = query.get("id").context(format!("no gallery id in {}", post_url))?;
+    let no = query.get("no").context(format!("no post number in {}", post_url))?;
+    let gall_type = if url.path().starts_with("/mgallery/") {
+        "M"
+    } else if url.path().starts_with("/mini/") {
+        "MI"
+    } else {
+        "G"
+    };
+
+    let html = utils::get_text_response(post_url, profile, None).await?;
+    let token = read_token(&html).context(format!("e_s_n_o token not found in {}", post_url))?;
+    let endpoint = url.join(COMMENT_PATH).context("Invalid comment URL")?;
+    if site.respect_robots && !robots::allowed(endpoint.as_str(), profile).await {
+        anyhow::bail!("robots.txt disallows {}", endpoint);
+    }
+
+    let mut total = 0;
+    let mut comments = vec![];
+    for page in 1..=max_pages {
+        let page = page.to_string();
+        let form = [
+            ("id", id.as_str()),
+            ("no", no.as_str()),
+            ("cmt_id", id.as_str()),
+            ("cmt_no", no.as_str()),
+            ("e_s_n_o", token.as_str()),
+            ("comment_page", page.as_str()),
+            ("sort", ""),
+            ("_GALLTYPE_", gall_type),
+        ];
+        let body = utils::post_form_text(endpoint.as_str(), &form, post_url, profile).await?;
+        let now = posttime::now_in(posttime::site_timezone(site));
+        let (page_total, page_comments) = parse_dc_comments(&body, &now)
+            .context(format!("comment page {} of {}", page, post_url))?;
+        total = page_total;
+        if page_comments.is_empty() {
+            break;
+        }
+        comments.extend(page_comments);
+        if comments.len() as u32 >= total {
+            break;
+        }
+    }
+    Ok((total, comments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const COMMENTS: &str = include_str!("../../fixtures/dc/comments.json");
+    const COMMENTS_EMPTY: &str = include_str!("../../fixtures/dc/comments_empty.json");
+
+    // 픽스처의 댓글은 10월 18일 12시 무렵에 달렸습니다.
+    fn now() -> DateTime<Tz> {
+        posttime::DEFAULT_TIMEZONE.with_ymd_and_hms(2024, 10, 18, 13, 0, 0).unwrap()
+    }
+
+    fn find<'a>(comments: &'a [Comment], no: &str) -> &'a Comment {
+        comments.iter().find(|c| c.no == no).unwrap()
+    }
+
+    #[test]
+    fn parses_comments_and_total() {
+        let (total, comments) = parse_dc_comments(COMMENTS, &now()).unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(comments.len(), 4);
+    }
+
+    #[test]
+    fn drops_comment_boy() {
+        let (_, comments) = parse_dc_comments(COMMENTS, &now()).unwrap();
+        assert!(comments.iter().all(|c| c.author != "댓글돌이"));
+    }
+
+    #[test]
+    fn replies_point_to_root_comment() {
+        let (_, comments) = parse_dc_comments(COMMENTS, &now()).unwrap();
+        let root = find(&comments, "3101");
+        assert_eq!(root.parent, None);
+        assert_eq!(root.depth, 0);
+        for no in ["3102", "3103"] {
+            let reply = find(&comments, no);
+            assert_eq!(reply.parent.as_deref(), Some("3101"), "{}", no);
+            assert_eq!(reply.depth, 1, "{}", no);
+        }
+    }
+
+    #[test]
+    fn marks_deleted() {
+        let (_, comments) = parse_dc_comments(COMMENTS, &now()).unwrap();
+        assert!(find(&comments, "3104").deleted);
+        assert!(!find(&comments, "3101").deleted);
+    }
+
+    #[test]
+    fn strips_html_from_text() {
+        let (_, comments) = parse_dc_comments(COMMENTS, &now()).unwrap();
+        assert_eq!(find(&comments, "3101").text, "첫 댓글 & 인사");
+        assert_eq!(find(&comments, "3102").text, "답글입니다");
+    }
+
+    #[test]
+    fn reads_comment_time_in_site_timezone() {
+        let (_, comments) = parse_dc_comments(COMMENTS, &now()).unwrap();
+        let expected = posttime::DEFAULT_TIMEZONE.with_ymd_and_hms(2024, 10, 18, 12, 1, 33).unwrap();
+        assert_eq!(find(&comments, "3101").datetime, "10.18 12:01:33");
+        assert_eq!(find(&comments, "3101").posted_at, Some(expected.timestamp()));
+        assert_eq!(find(&comments, "3101").posted_at, Some(1729220493));
+        assert!(comments.iter().all(|c| c.posted_at.is_some()));
+    }
+
+    #[test]
+    fn empty_response() {
+        let (total, comments) = parse_dc_comments(COMMENTS_EMPTY, &now()).unwrap();
+        assert_eq!(total, 0);
+        assert!(comments.is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+use anyhow::Result;
+use crate::models::{DetailSpec, PostDetail};
+
+fn compile(name: &str, css: &str) -> Result<Selector> {
+    Selector::parse(css).map_err(|e| anyhow::anyhow!("invalid detail.{} selector {:?}: {:?}", name, css, e))
+}
+
+/// 설정의 선택자들이 올바른지 확인합니다.
+pub fn check(spec: &DetailSpec) -> Result<()> {
+    let fields = [
+        ("author", &spec.author),
+        ("body", &spec.body),
+        ("image", &spec.image),
+        ("views", &spec.views),
+        ("recommends", &spec.recommends),
+        ("comments", &spec.comments),
+    ];
+    for (name, css) in fields {
+        if let Some(css) = css {
+            compile(name, css)?;
+        }
+    }
+    Ok(())
+}
+
+/// 텍스트에서 숫자만 모아 읽습니다. (`"조회 1,234"` → 1234)
+fn read_count(text: &str) -> Option<u64> {
+    let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// 요소 안의 텍스트를 줄 단위로 모읍니다. 빈 줄은 버립니다.
+fn read_text(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::lines)
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 상세 페이지를 선택자 정의대로 읽습니다. 찾지 못한 항목은 비워 둡니다.
+pub fn parse(spec: &DetailSpec, html: &str, page_url: &str) -> Result<PostDetail> {
+    let document = Html::parse_document(html);
+    let base = Url::parse(page_url).ok();
+    let first = |name: &str, css: &Option<String>| -> Result<Option<ElementRef<'_>>> {
+        match css {
+            Some(css) => Ok(document.select(&compile(name, css)?).next()),
+            None => Ok(None),
+        }
+    };
+
+    let author = first("author", &spec.author)?
+        .map(|v| match &spec.author_attr {
+            Some(attr) => v.value().attr(attr).unwrap_or_default().to_string(),
+            None => read_text(v),
+        })
+        .unwrap_or_default();
+    let body_element = first("body", &spec.body)?;
+    let body = body_element.map(read_text).unwrap_or_default();
+
+    let image_sel = compile("image", spec.image.as_deref().unwrap_or("img"))?;
+    let image_elements: Vec<ElementRef> = match (&spec.image, body_element) {
+        (Some(_), _) => document.select(&image_sel).collect(),
+        (None, Some(b)) => b.select(&image_sel).collect(),
+        (None, None) => vec![],
+    };
+    let images = image_elements
+        .into_iter()
+        .filter_map(|v| v.value().attr(&spec.image_attr))
+        .filter(|src| !src.is_empty())
+        .map(|src| {
+            base.as_ref()
+                .and_then(|b| b.join(src).ok())
+                .map(|u| u.to_string())
+                .unwrap_or_else(|| src.to_string())
+        })
+        .collect();
+
+    let count = |name: &str, css: &Option<String>| -> Result<Option<u64>> {
+        Ok(first(name, css)?.and_then(|v| read_count(&read_text(v))))
+    };
+    Ok(PostDetail {
+        author,
+        body,
+        images,
+        views: count("views", &spec.views)?,
+        recommends: count("recommends", &spec.recommends)?,
+        comments: count("comments", &spec.comments)?,
+    })
+}
@@ -1,29 +1,62 @@
 use scraper::{Html, Selector};
-use chrono::Utc;
-use chrono_tz::Asia::Seoul;
+use url::Url;
+use chrono_tz::Tz;
 use anyhow::Result;
-use crate::models::List;
+use crate::models::{Nick, Post, PostFlags, Site};
+use crate::posttime;
+use super::{links, title, Scraper};
 
-pub fn parse_fm(html: &str) -> Result<Vec<List>> {
-    let mut _list: Vec<List> = vec![];
-    let _today = Utc::now().with_timezone(&Seoul);
+pub struct FmScraper;
+
+impl Scraper for FmScraper {
+    fn host(&self) -> &str {
+        "fm"
+    }
+
+    fn label(&self) -> &str {
+        "펨코"
+    }
+
+    fn link_params(&self) -> Option<Vec<&str>> {
+        Some(vec!["mid", "document_srl"])
+    }
+
+    fn parse(&self, html: &str, site: &Site, _nick_list: &[Nick]) -> Result<Vec<Post>> {
+        parse_fm(html, &site.url, posttime::site_timezone(site))
+    }
+}
+
+pub fn parse_fm(html: &str, page_url: &str, tz: Tz) -> Result<Vec<Post>> {
+    let mut _list: Vec<Post> = vec![];
+    let base = Url::parse(page_url).ok();
+    let _today = posttime::now_in(tz);
     let fragment = Html::parse_fragment(html);
     let part_sel = Selector::parse("div.li").map_err(|_| anyhow::anyhow!("Invalid fm selector"))?;
     let a_sel = Selector::parse("h3.title > a").map_err(|_| anyhow::anyhow!("Invalid fm a selector"))?;
+    let date_sel = Selector::parse("span.regdate").map_err(|_| anyhow::anyhow!("Invalid fm date selector"))?;
+    let count_sel = Selector::parse("span.comment_count").map_err(|_| anyhow::anyhow!("Invalid fm count selector"))?;
     
     for element in fragment.select(&part_sel) {
+        let _date = element
+            .select(&date_sel)
+            .next()
+            .map(|v| v.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+        let _posted = posttime::parse(&_date, &_today).map(|v| v.timestamp());
         for _li in element.select(&a_sel) {
-            let _title = _li.inner_html();
-            let _link = _li.value().attr("href").unwrap_or_default();
+            let (_title, _comment_count) = title::normalize(_li, Some(&count_sel));
+            let _link = links::resolve(base.as_ref(), _li.value().attr("href").unwrap_or_default());
 
-            _list.push(List {
-                timestamp: _today.timestamp(),
+            _list.push(Post {
                 title: _title,
-                datetime: String::new(),
-                link: _link.to_string(),
-                images: String::new(),
-                more: "펨코".to_string(),
-                new: true,
+                comment_count: _comment_count,
+                link: _link,
+                datetime: _date.clone(),
+                timestamp: _posted.unwrap_or(_today.timestamp()),
+                posted_at: _posted,
+                first_seen_at: _today.timestamp(),
+                flags: PostFlags { new: true, ..Default::default() },
+                ..Default::default()
             });
         }
     }
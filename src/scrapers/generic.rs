@@ -0,0 +1,152 @@
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+use anyhow::Result;
+use crate::models::{FetchMode, Nick, Post, PostFlags, SelectorSpec, Site};
+use crate::posttime;
+use super::{links, title, Scraper, LIST_MAX_AGE_SECS};
+
+/// 설정의 `SelectorSpec` 으로 동작하는 범용 목록 파서
+pub struct GenericScraper {
+    host: String,
+    label: String,
+    site_id: String,
+    link_params: Option<Vec<String>>,
+    fetch_mode: FetchMode,
+    row: Selector,
+    title: Selector,
+    link_attr: String,
+    date: Option<Selector>,
+    date_attr: Option<String>,
+    date_format: Option<String>,
+    author: Option<Selector>,
+    author_attr: Option<String>,
+    comment_count: Option<Selector>,
+}
+
+fn compile(name: &str, css: &str) -> Result<Selector> {
+    Selector::parse(css).map_err(|e| anyhow::anyhow!("invalid {} selector {:?}: {:?}", name, css, e))
+}
+
+/// 요소의 속성값 또는 텍스트를 읽습니다.
+fn read_value(element: ElementRef, attr: Option<&str>) -> String {
+    match attr {
+        Some(a) => element.value().attr(a).unwrap_or_default().to_string(),
+        None => element.text().collect::<String>().trim().to_string(),
+    }
+}
+
+impl GenericScraper {
+    pub fn new(host: &str, spec: &SelectorSpec) -> Result<Self> {
+        Ok(GenericScraper {
+            host: host.to_string(),
+            label: spec.label.clone(),
+            site_id: spec.site.clone().unwrap_or_else(|| host.to_string()),
+            link_params: spec.link_params.clone(),
+            fetch_mode: spec.fetch_mode,
+            row: compile("row", &spec.row)?,
+            title: compile("title", &spec.title)?,
+            link_attr: spec.link_attr.clone(),
+            date: spec.date.as_deref().map(|css| compile("date", css)).transpose()?,
+            date_attr: spec.date_attr.clone(),
+            date_format: spec.date_format.clone(),
+            author: spec.author.as_deref().map(|css| compile("author", css)).transpose()?,
+            author_attr: spec.author_attr.clone(),
+            comment_count: spec
+                .comment_count
+                .as_deref()
+                .map(|css| compile("comment_count", css))
+                .transpose()?,
+        })
+    }
+}
+
+impl Scraper for GenericScraper {
+    fn host(&self) -> &str {
+        &self.host
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn site_id(&self) -> &str {
+        &self.site_id
+    }
+
+    fn fetch_mode(&self) -> FetchMode {
+        self.fetch_mode
+    }
+
+    fn link_params(&self) -> Option<Vec<&str>> {
+        self.link_params
+            .as_ref()
+            .map(|v| v.iter().map(String::as_str).collect())
+    }
+
+    fn parse(&self, html: &str, site: &Site, nick_list: &[Nick]) -> Result<Vec<Post>> {
+        let mut _list: Vec<Post> = vec![];
+        let tz = posttime::site_timezone(site);
+        let _today = posttime::now_in(tz);
+        let fragment = Html::parse_fragment(html);
+        let base = Url::parse(&site.url).ok();
+
+        for row in fragment.select(&self.row) {
+            let _a = match row.select(&self.title).next() {
+                Some(v) => v,
+                None => continue,
+            };
+            let _href = _a.value().attr(&self.link_attr).unwrap_or_default();
+            if _href.is_empty() {
+                continue;
+            }
+            let _link = links::resolve(base.as_ref(), _href);
+
+            let _nick = self
+                .author
+                .as_ref()
+                .and_then(|sel| row.select(sel).next())
+                .map(|v| read_value(v, self.author_attr.as_deref()))
+                .unwrap_or_default();
+            if !_nick.is_empty() && nick_list.iter().any(|e| _nick == e.nick) {
+                continue;
+            }
+
+            let _date = self
+                .date
+                .as_ref()
+                .and_then(|sel| row.select(sel).next())
+                .map(|v| read_value(v, self.date_attr.as_deref()))
+                .unwrap_or_default();
+
+            // 형식이 지정되어 있으면 그 형식으로, 아니면 흔한 표기(`HH:MM`, `N분 전` 등)로 해석합니다.
+            let _posted = match &self.date_format {
+                Some(fmt) => chrono::NaiveDateTime::parse_from_str(&_date, fmt)
+                    .ok()
+                    .and_then(|v| posttime::localize(v, &tz)),
+                None => posttime::parse(&_date, &_today),
+            }
+            .map(|v| v.timestamp());
+            // 해석에 성공한 경우에만 오래된 글을 걸러냅니다.
+            if let Some(v) = _posted
+                && _today.timestamp() - v >= LIST_MAX_AGE_SECS
+            {
+                continue;
+            }
+
+            let (_title, _comment_count) = title::normalize(_a, self.comment_count.as_ref());
+            _list.push(Post {
+                title: _title,
+                comment_count: _comment_count,
+                link: _link,
+                author: _nick,
+                datetime: _date,
+                timestamp: _posted.unwrap_or(_today.timestamp()),
+                posted_at: _posted,
+                first_seen_at: _today.timestamp(),
+                flags: PostFlags { new: true, ..Default::default() },
+                ..Default::default()
+            });
+        }
+        Ok(_list)
+    }
+}
@@ -0,0 +1,98 @@
+use url::Url;
+
+// 글을 가리키는 데 쓰이지 않는 추적용 파라미터 (`utm_` 로 시작하는 것도 지웁니다)
+const TRACKING_PARAMS: [&str; 4] = ["fbclid", "gclid", "ref", "referer"];
+
+/// `href` 를 목록 페이지 주소 기준의 절대 주소로 바꿉니다. 기준이 없거나 합칠 수 없으면 그대로 둡니다.
+pub fn resolve(base: Option<&Url>, href: &str) -> String {
+    base.and_then(|b| b.join(href.trim()).ok())
+        .map(|u| u.to_string())
+        .unwrap_or_else(|| href.to_string())
+}
+
+fn is_tracking(key: &str) -> bool {
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key)
+}
+
+/// 같은 글이 항상 같은 주소가 되도록 정리합니다.
+///
+/// `params` 가 있으면 그 쿼리 파라미터만 그 순서대로 남기고, 없으면 추적용 파라미터만 지웁니다.
+/// 조각(`#comment`)은 항상 지웁니다. 절대 주소가 아니면 그대로 둡니다.
+pub fn canonicalize(link: &str, params: Option<&[&str]>) -> String {
+    let mut url = match Url::parse(link) {
+        Ok(v) => v,
+        Err(_) => return link.to_string(),
+    };
+    url.set_fragment(None);
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let kept: Vec<(String, String)> = match params {
+        Some(params) => params
+            .iter()
+            .filter_map(|key| pairs.iter().find(|(k, _)| k == key).cloned())
+            .collect(),
+        None => pairs.into_iter().filter(|(k, _)| !is_tracking(k)).collect(),
+    };
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_href() {
+        let base = Url::parse("https://gall.dcinside.com/board/lists/?id=hit&page=2").unwrap();
+        assert_eq!(
+            resolve(Some(&base), " /board/view/?id=hit&no=1 "),
+            "https://gall.dcinside.com/board/view/?id=hit&no=1"
+        );
+        assert_eq!(
+            resolve(Some(&base), "view/?id=hit&no=1"),
+            "https://gall.dcinside.com/board/lists/view/?id=hit&no=1"
+        );
+        assert_eq!(
+            resolve(Some(&base), "https://m.dcinside.com/board/hit/1"),
+            "https://m.dcinside.com/board/hit/1"
+        );
+        assert_eq!(resolve(None, "/board/view/?no=1"), "/board/view/?no=1");
+    }
+
+    #[test]
+    fn keeps_only_listed_params_in_order() {
+        let link = "https://gall.dcinside.com/board/view/?page=3&no=12345&exception_mode=recommend&id=hit";
+        assert_eq!(
+            canonicalize(link, Some(&["id", "no"])),
+            "https://gall.dcinside.com/board/view/?id=hit&no=12345"
+        );
+        assert_eq!(
+            canonicalize("https://gall.dcinside.com/board/view/?page=3", Some(&["id", "no"])),
+            "https://gall.dcinside.com/board/view/"
+        );
+    }
+
+    #[test]
+    fn drops_tracking_params() {
+        let link = "https://example.com/post?b=1&utm_source=tw&utm_medium=x&fbclid=abc&a=2&ref=home";
+        assert_eq!(canonicalize(link, None), "https://example.com/post?b=1&a=2");
+        assert_eq!(canonicalize("https://example.com/post?gclid=1", None), "https://example.com/post");
+    }
+
+    #[test]
+    fn drops_fragment() {
+        assert_eq!(
+            canonicalize("https://www.fmkorea.com/index.php?mid=best&document_srl=1#comment", Some(&["mid", "document_srl"])),
+            "https://www.fmkorea.com/index.php?mid=best&document_srl=1"
+        );
+        assert_eq!(canonicalize("https://example.com/p/1#top", None), "https://example.com/p/1");
+    }
+
+    #[test]
+    fn relative_link_left_alone() {
+        assert_eq!(canonicalize("/p?id=1#c", Some(&["id"])), "/p?id=1#c");
+    }
+}
@@ -1,3 +1,266 @@
 pub mod dc;
+pub mod dc_comments;
+pub mod detail;
 pub mod fm;
+pub mod generic;
+pub mod links;
 pub mod mp;
+pub mod pagination;
+pub mod postid;
+pub mod title;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use url::Url;
+use async_trait::async_trait;
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use log::warn;
+use crate::models::{Comment, DetailSpec, Images, Nick, Post, Site};
+use crate::{clients, robots, utils};
+
+pub use crate::models::FetchMode;
+
+// 목록에서 이보다 오래된 게시물은 건너뜁니다 (24시간)
+pub const LIST_MAX_AGE_SECS: i64 = 86400;
+
+/// 게시판 하나를 담당하는 스크래퍼
+///
+/// 새 게시판을 추가할 때는 이 트레이트를 구현한 모듈을 만들고 `REGISTRY`에 등록하거나,
+/// 설정의 `Site.selectors` 로 범용 파서를 사용합니다.
+#[async_trait]
+pub trait Scraper: Send + Sync {
+    /// `Site.host` / `Save.host` 에 쓰이는 키
+    fn host(&self) -> &str;
+
+    /// 게시물에 붙는 표시 라벨 (`Post.label`)
+    fn label(&self) -> &str;
+
+    /// 게시물의 사이트 id (`Post.site`). 같은 id 를 내는 스크래퍼끼리 저장 파일을 함께 씁니다.
+    fn site_id(&self) -> &str {
+        self.host()
+    }
+
+    fn fetch_mode(&self) -> FetchMode {
+        FetchMode::Default
+    }
+
+    fn parse(&self, html: &str, site: &Site, nick_list: &[Nick]) -> Result<Vec<Post>>;
+
+    /// 게시물 주소에 남길 쿼리 파라미터. `None` 이면 추적용 파라미터만 지웁니다.
+    fn link_params(&self) -> Option<Vec<&str>> {
+        None
+    }
+
+    /// 게시물 링크를 사이트의 정규 주소로 바꿉니다.
+    fn canonical_link(&self, link: &str) -> String {
+        links::canonicalize(link, self.link_params().as_deref())
+    }
+
+    /// 게시물 링크에서 (게시판 id, 글 번호)를 읽습니다. 기본값은 흔한 쿼리/경로 형태로 추측합니다.
+    fn post_key(&self, url: &Url) -> Option<(String, String)> {
+        postid::guess(url)
+    }
+
+    /// 게시물의 `site`, `board`, `post_no`, `id` 를 링크로부터 채웁니다.
+    fn identify(&self, post: &mut Post) {
+        post.site = self.site_id().to_string();
+        match postid::parse_link(&post.link).and_then(|u| self.post_key(&u)) {
+            Some((board, post_no)) => {
+                post.id = format!("{}:{}:{}", post.site, board, post_no);
+                post.board = board;
+                post.post_no = post_no;
+            }
+            None => post.id = format!("{}:{}", post.site, post.link),
+        }
+    }
+
+    /// 목록 한 페이지를 파싱하고 링크를 정규 주소로 바꾼 뒤 식별자와 표시 라벨을 붙입니다.
+    fn parse_page(&self, html: &str, site: &Site, nick_list: &[Nick]) -> Result<Vec<Post>> {
+        let mut posts = self.parse(html, site, nick_list)?;
+        for post in posts.iter_mut() {
+            post.link = self.canonical_link(&post.link);
+            post.label = self.label().to_string();
+            self.identify(post);
+        }
+        Ok(posts)
+    }
+
+    /// 게시물 본문에서 다운로드할 이미지를 추출합니다. 기본값은 지원하지 않음.
+    fn parse_images(&self, _html: &str, _path: &str, _title: &str, _host: &str) -> Result<Vec<Images>> {
+        Ok(vec![])
+    }
+
+    /// 댓글을 모을 수 있는 스크래퍼인지. `true` 면 `collect_comments` 를 구현합니다.
+    fn supports_comments(&self) -> bool {
+        false
+    }
+
+    /// 게시물의 댓글을 최대 `max_pages` 페이지까지 모읍니다. (전체 댓글 수, 댓글)
+    ///
+    /// 작성 시각은 `site` 의 시간대로 읽고, `site.respect_robots` 면 게시물 외에 따로 요청하는 주소도
+    /// robots.txt 로 확인합니다.
+    async fn collect_comments(
+        &self,
+        _post_url: &str,
+        _site: &Site,
+        _profile: &str,
+        _max_pages: u32,
+    ) -> Result<(u32, Vec<Comment>)> {
+        anyhow::bail!("comments are not supported for {}", self.host())
+    }
+
+    /// `"detail": {}` 처럼 선택자 없이 상세 수집을 켰을 때 쓰는 내장 선택자 정의
+    fn detail_spec(&self) -> Option<DetailSpec> {
+        None
+    }
+
+    /// 사이트 설정에 프로필이 없을 때 사용할 HTTP 클라이언트 프로필
+    fn default_profile(&self) -> &str {
+        clients::profile_for(self.fetch_mode())
+    }
+
+    /// 목록을 받아 파싱합니다. 첫 페이지가 지난번과 같으면(304) `None` 을 돌려줍니다.
+    ///
+    /// `site.pagination` 이 있으면 다음 페이지들도 받아 한 목록으로 합칩니다.
+    /// `seen` 은 이미 저장된 게시물을 찾는 데 쓰며, `stop_when_seen` 판단에 씁니다.
+    /// 첫 페이지의 검증자는 저장하지 않고 돌려주므로, 호출하는 쪽이 저장을 마친 뒤 `Scraped::commit` 합니다.
+    async fn scrape(&self, site: &Site, nick_list: &[Nick], seen: &dyn SeenIds) -> Result<Option<Scraped>> {
+        let profile = site.profile.as_deref().unwrap_or(self.default_profile());
+        let encoding = site.encoding.as_deref();
+        if site.respect_robots && !robots::allowed(&site.url, profile).await {
+            return Ok(Some(Scraped { posts: vec![], page: None }));
+        }
+        let page = match utils::get_text_if_modified(&site.url, profile, encoding).await? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let mut posts = self
+            .parse_page(&page.body, site, nick_list)
+            .context(format!("parse failed for {}", site.host))?;
+        let spec = match &site.pagination {
+            Some(v) if v.max_pages > 1 => v,
+            _ => return Ok(Some(Scraped { posts, page: Some(page) })),
+        };
+
+        let mut ids: HashSet<String> = posts.iter().map(|p| p.id.clone()).collect();
+        let mut visited: HashSet<String> = HashSet::from([site.url.clone()]);
+        let mut current_url = site.url.clone();
+        let mut current_html = page.body.clone();
+        let mut last_page = posts.clone();
+
+        for n in 2..=spec.max_pages {
+            if last_page.is_empty() {
+                break;
+            }
+            if spec.stop_when_seen {
+                let page_ids: Vec<String> = last_page.iter().map(|p| p.id.clone()).collect();
+                if pagination::all_seen(&last_page, &seen.known(&page_ids).await) {
+                    break;
+                }
+            }
+            let url = match pagination::next_url(spec, &site.url, n, &current_url, &current_html)? {
+                Some(v) if visited.insert(v.clone()) => v,
+                _ => break,
+            };
+            if site.respect_robots && !robots::allowed(&url, profile).await {
+                break;
+            }
+            let html = match utils::get_text_response(&url, profile, encoding).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("{}: stopped at page {}: {}", site.host, n, e);
+                    break;
+                }
+            };
+            last_page = self
+                .parse_page(&html, site, nick_list)
+                .context(format!("parse failed for {} page {}", site.host, n))?;
+            posts.extend(last_page.iter().filter(|p| ids.insert(p.id.clone())).cloned());
+            current_url = url;
+            current_html = html;
+        }
+        Ok(Some(Scraped { posts, page: Some(page) }))
+    }
+}
+
+/// 이미 저장된 게시물 id 를 찾아 주는 곳 (`storage::SavedIds`)
+#[async_trait]
+pub trait SeenIds: Send + Sync {
+    /// `ids` 가운데 이미 저장된 id
+    async fn known(&self, ids: &[String]) -> HashSet<String>;
+}
+
+/// `Scraper::scrape` 의 결과
+pub struct Scraped {
+    pub posts: Vec<Post>,
+    page: Option<utils::Page>,
+}
+
+impl Scraped {
+    /// 첫 페이지의 검증자를 저장해 다음 요청부터 조건부로 보냅니다. 게시물을 모두 저장한 뒤에 부릅니다.
+    pub fn commit(&mut self) {
+        if let Some(page) = self.page.take() {
+            page.remember();
+        }
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: HashMap<String, Arc<dyn Scraper>> = {
+        let scrapers: Vec<Arc<dyn Scraper>> = vec![
+            Arc::new(dc::DcScraper),
+            Arc::new(fm::FmScraper),
+            Arc::new(mp::MpScraper),
+            Arc::new(mp::MpLowScraper),
+        ];
+        scrapers.into_iter().map(|s| (s.host().to_string(), s)).collect()
+    };
+}
+
+/// `host` 키로 등록된 내장 스크래퍼를 찾습니다.
+pub fn find(host: &str) -> Option<Arc<dyn Scraper>> {
+    REGISTRY.get(host).cloned()
+}
+
+/// 사이트 설정에 맞는 스크래퍼를 만듭니다. `selectors` 가 있으면 내장 스크래퍼보다 우선합니다.
+pub fn for_site(site: &Site) -> Result<Arc<dyn Scraper>> {
+    match &site.selectors {
+        Some(spec) => {
+            let scraper = generic::GenericScraper::new(&site.host, spec)
+                .context(format!("invalid selectors for site {}", site.host))?;
+            Ok(Arc::new(scraper))
+        }
+        None => find(&site.host).ok_or_else(|| anyhow::anyhow!("unknown site host: {}", site.host)),
+    }
+}
+
+/// 사이트에 적용할 상세 페이지 선택자. 설정에 선택자가 없으면 스크래퍼의 내장 정의를 씁니다.
+pub fn detail_spec_for(site: &Site, scraper: &dyn Scraper) -> Option<DetailSpec> {
+    let spec = site.detail.as_ref()?;
+    if spec.is_builtin() {
+        scraper.detail_spec()
+    } else {
+        Some(spec.clone())
+    }
+}
+
+/// `Save.host` 에 해당하는 스크래퍼. 같은 host 의 사이트 설정이 있으면 그 정의를 씁니다.
+pub fn for_save(host: &str, sites: &[Site]) -> Option<Arc<dyn Scraper>> {
+    match sites.iter().find(|s| s.host == host) {
+        Some(site) => for_site(site).ok(),
+        None => find(host),
+    }
+}
+
+/// `Save.host` 가 받는 게시물의 사이트 id
+pub fn site_id_for(host: &str, sites: &[Site]) -> Option<String> {
+    for_save(host, sites).map(|s| s.site_id().to_string())
+}
+
+/// 등록된 내장 스크래퍼의 host 목록 (정렬됨)
+pub fn known_hosts() -> Vec<&'static str> {
+    let mut known: Vec<&str> = REGISTRY.keys().map(String::as_str).collect();
+    known.sort_unstable();
+    known
+}
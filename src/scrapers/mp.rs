@@ -1,17 +1,79 @@
 use scraper::{Html, Selector};
-use chrono::Utc;
-use chrono_tz::Asia::Seoul;
+use url::Url;
+use chrono_tz::Tz;
 use anyhow::Result;
-use crate::models::List;
+use crate::models::{Nick, Post, PostFlags, Site};
+use crate::posttime;
+use super::{links, title, FetchMode, Scraper};
 
-pub fn parse_mp(html: &str) -> Result<Vec<List>> {
-    let mut _list: Vec<List> = vec![];
+// 글을 가리키는 쿼리 파라미터 (목록 페이지 번호 `p` 등은 버립니다)
+const MP_LINK_PARAMS: [&str; 3] = ["m", "b", "id"];
+// 제목 링크 안에서 댓글 수를 감싼 요소
+const MP_COUNTER: &str = "span.replycnt";
+
+pub struct MpScraper;
+
+impl Scraper for MpScraper {
+    fn host(&self) -> &str {
+        "mp"
+    }
+
+    fn label(&self) -> &str {
+        "엠팍"
+    }
+
+    fn fetch_mode(&self) -> FetchMode {
+        FetchMode::Bot
+    }
+
+    fn link_params(&self) -> Option<Vec<&str>> {
+        Some(MP_LINK_PARAMS.to_vec())
+    }
+
+    fn parse(&self, html: &str, site: &Site, _nick_list: &[Nick]) -> Result<Vec<Post>> {
+        parse_mp(html, &site.url, posttime::site_timezone(site))
+    }
+}
+
+/// 엠팍 메인의 "오늘의 글" 영역. 저장은 `mp` 와 같은 목록을 사용합니다.
+pub struct MpLowScraper;
+
+impl Scraper for MpLowScraper {
+    fn host(&self) -> &str {
+        "mp_low"
+    }
+
+    fn label(&self) -> &str {
+        "엠팍"
+    }
+
+    fn site_id(&self) -> &str {
+        "mp"
+    }
+
+    fn fetch_mode(&self) -> FetchMode {
+        FetchMode::Bot
+    }
+
+    fn link_params(&self) -> Option<Vec<&str>> {
+        Some(MP_LINK_PARAMS.to_vec())
+    }
+
+    fn parse(&self, html: &str, site: &Site, _nick_list: &[Nick]) -> Result<Vec<Post>> {
+        parse_mp_part_low(html, &site.url, posttime::site_timezone(site))
+    }
+}
+
+pub fn parse_mp(html: &str, page_url: &str, tz: Tz) -> Result<Vec<Post>> {
+    let mut _list: Vec<Post> = vec![];
+    let base = Url::parse(page_url).ok();
     let fragment = Html::parse_fragment(html);
-    let _today = Utc::now().with_timezone(&Seoul);
+    let _today = posttime::now_in(tz);
     let table_sel = Selector::parse("table.tbl_type01").map_err(|_| anyhow::anyhow!("Invalid mp table selector"))?;
     let tr_sel = Selector::parse("tbody > tr").map_err(|_| anyhow::anyhow!("Invalid mp tr selector"))?;
     let a_sel = Selector::parse("td.t_left > a").map_err(|_| anyhow::anyhow!("Invalid mp a selector"))?;
     let date_sel = Selector::parse("td > span.date").map_err(|_| anyhow::anyhow!("Invalid mp date selector"))?;
+    let count_sel = Selector::parse(MP_COUNTER).map_err(|_| anyhow::anyhow!("Invalid mp count selector"))?;
 
     for _table in fragment.select(&table_sel) {
         for _tr in _table.select(&tr_sel) {
@@ -24,31 +86,37 @@ pub fn parse_mp(html: &str) -> Result<Vec<List>> {
                 None => continue,
             };
             
-            let _link = _a.value().attr("href").unwrap_or_default();
-            let _title = _a.inner_html();
+            let _link = links::resolve(base.as_ref(), _a.value().attr("href").unwrap_or_default());
+            let (_title, _comment_count) = title::normalize(_a, Some(&count_sel));
+            let _date_text = _date.inner_html();
+            let _posted = posttime::parse(&_date_text, &_today).map(|v| v.timestamp());
             
-            _list.push(List {
-                timestamp: _today.timestamp(),
+            _list.push(Post {
                 title: _title,
-                datetime: _date.inner_html(),
-                link: _link.to_string(),
-                images: String::new(),
-                more: "엠팍".to_string(),
-                new: true,
+                comment_count: _comment_count,
+                link: _link,
+                datetime: _date_text,
+                timestamp: _posted.unwrap_or(_today.timestamp()),
+                posted_at: _posted,
+                first_seen_at: _today.timestamp(),
+                flags: PostFlags { new: true, ..Default::default() },
+                ..Default::default()
             });
         }
     }
     Ok(_list)
 }
 
-pub fn parse_mp_part_low(html: &str) -> Result<Vec<List>> {
-    let mut _list: Vec<List> = vec![];
-    let _today = Utc::now().with_timezone(&Seoul);
+pub fn parse_mp_part_low(html: &str, page_url: &str, tz: Tz) -> Result<Vec<Post>> {
+    let mut _list: Vec<Post> = vec![];
+    let base = Url::parse(page_url).ok();
+    let _today = posttime::now_in(tz);
     let fragment = Html::parse_fragment(html);
 
     let div_sel = Selector::parse("div.lists_today_contxt").map_err(|_| anyhow::anyhow!("Invalid mp low div selector"))?;
     let li_sel = Selector::parse("li.items").map_err(|_| anyhow::anyhow!("Invalid mp low li selector"))?;
     let a_sel = Selector::parse("a").map_err(|_| anyhow::anyhow!("Invalid mp low a selector"))?;
+    let count_sel = Selector::parse(MP_COUNTER).map_err(|_| anyhow::anyhow!("Invalid mp low count selector"))?;
     
     for _div in fragment.select(&div_sel) {
         for _li in _div.select(&li_sel) {
@@ -56,17 +124,17 @@ pub fn parse_mp_part_low(html: &str) -> Result<Vec<List>> {
                 Some(v) => v,
                 None => continue,
             };
-            let _link = _a.value().attr("href").unwrap_or_default();
-            let _title = _a.inner_html();
+            let _link = links::resolve(base.as_ref(), _a.value().attr("href").unwrap_or_default());
+            let (_title, _comment_count) = title::normalize(_a, Some(&count_sel));
             
-            _list.push(List {
-                timestamp: _today.timestamp(),
+            _list.push(Post {
                 title: _title,
-                datetime: String::new(),
-                link: _link.to_string(),
-                images: String::new(),
-                more: "엠팍".to_string(),
-                new: true,
+                comment_count: _comment_count,
+                link: _link,
+                timestamp: _today.timestamp(),
+                first_seen_at: _today.timestamp(),
+                flags: PostFlags { new: true, ..Default::default() },
+                ..Default::default()
             });
         }
     }
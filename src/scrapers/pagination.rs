@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+use scraper::{Html, Selector};
+use url::Url;
+use anyhow::Result;
+use crate::models::{Pagination, Post};
+
+/// `base` 의 `param` 쿼리 파라미터를 `n` 으로 바꾼 주소
+fn with_page_param(base: &str, param: &str, n: u32) -> Option<String> {
+    let mut url = Url::parse(base).ok()?;
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| k != param)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(param, &n.to_string());
+    Some(url.to_string())
+}
+
+/// 현재 페이지에서 다음 페이지 링크를 찾아 절대 주소로 바꿉니다.
+fn next_link(html: &str, selector: &str, current_url: &str) -> Result<Option<String>> {
+    let selector = Selector::parse(selector)
+        .map_err(|e| anyhow::anyhow!("invalid next_selector {:?}: {:?}", selector, e))?;
+    let document = Html::parse_document(html);
+    let href = match document
+        .select(&selector)
+        .find_map(|a| a.value().attr("href"))
+    {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let url = Url::parse(current_url).and_then(|base| base.join(href)).ok();
+    Ok(url
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .map(|u| u.to_string()))
+}
+
+/// n 번째 페이지 주소. `page_param` 이 있으면 쿼리 파라미터를 바꾸고,
+/// 없으면 현재 페이지의 `next_selector` 링크를 따릅니다. 더 없으면 `None` 입니다.
+pub fn next_url(
+    spec: &Pagination,
+    base: &str,
+    n: u32,
+    current_url: &str,
+    current_html: &str,
+) -> Result<Option<String>> {
+    if let Some(param) = &spec.page_param {
+        return Ok(with_page_param(base, param, n));
+    }
+    match &spec.next_selector {
+        Some(selector) => next_link(current_html, selector, current_url),
+        None => Ok(None),
+    }
+}
+
+/// 페이지의 게시물이 모두 이미 저장된 것인지 확인합니다.
+pub fn all_seen(posts: &[Post], seen: &HashSet<String>) -> bool {
+    posts.iter().all(|p| seen.contains(&p.id))
+}
+
+/// 설정의 `next_selector` 가 올바른 선택자인지 확인합니다.
+pub fn check_selector(selector: &str) -> Result<()> {
+    Selector::parse(selector)
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("invalid selector {:?}: {:?}", selector, e))
+}
@@ -0,0 +1,49 @@
+use url::Url;
+
+// 상대 링크(`/best/123`)를 읽을 때 붙이는 임시 기준 주소
+const RELATIVE_BASE: &str = "http://localhost/";
+// 글 번호가 들어가는 쿼리 파라미터 (앞의 것이 우선)
+const POST_NO_KEYS: [&str; 6] = ["no", "document_srl", "wr_id", "idx", "num", "id"];
+// 게시판 id 가 들어가는 쿼리 파라미터 (앞의 것이 우선)
+const BOARD_KEYS: [&str; 6] = ["bo_table", "mid", "board", "bid", "b", "id"];
+
+/// 절대/상대 링크를 모두 URL 로 읽습니다.
+pub fn parse_link(link: &str) -> Option<Url> {
+    Url::parse(link)
+        .or_else(|_| Url::parse(RELATIVE_BASE).and_then(|base| base.join(link)))
+        .ok()
+}
+
+fn is_post_no(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// 링크에서 (게시판 id, 글 번호)를 추측합니다.
+///
+/// 쿼리에 숫자 글 번호(`no`, `document_srl`, `wr_id` ...)가 있으면 남은 게시판 파라미터와 함께 쓰고,
+/// 없으면 경로의 마지막 숫자 조각을 글 번호로, 그 앞 조각을 게시판으로 봅니다.
+/// (`?id=baseball&no=123`, `?b=bullpen&id=2024...`, `/best/7723`)
+pub fn guess(url: &Url) -> Option<(String, String)> {
+    let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let value = |key: &str| query.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+    let post_key = POST_NO_KEYS
+        .into_iter()
+        .find(|key| value(key).is_some_and(is_post_no));
+    if let Some(post_key) = post_key {
+        let board = BOARD_KEYS
+            .into_iter()
+            .filter(|key| *key != post_key)
+            .find_map(value)
+            .unwrap_or_default();
+        return Some((board.to_string(), value(post_key)?.to_string()));
+    }
+
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let pos = segments.iter().rposition(|s| is_post_no(s))?;
+    let board = match pos {
+        0 => "",
+        _ => segments[pos - 1],
+    };
+    Some((board.to_string(), segments[pos].to_string()))
+}
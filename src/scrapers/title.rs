@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use scraper::{ElementRef, Html, Selector};
+
+// 이전 버전이 `inner_html` 그대로 저장한 제목 안의 댓글 수 요소 (fm / mp)
+const LEGACY_COUNTERS: &str = "span.comment_count, span.replycnt";
+
+/// 요소의 텍스트 노드만 모아 제목으로 정리합니다. (제목, 댓글 수)
+///
+/// 아이콘을 감싼 태그는 버리고 글자만 남깁니다. 엔티티(`&amp;`)는 HTML 파서가 풀어 줍니다.
+/// `counter` 에 맞는 요소는 제목에서 빼고 그 텍스트를 댓글 수로 읽습니다.
+pub fn normalize(element: ElementRef, counter: Option<&Selector>) -> (String, Option<u32>) {
+    let counters: Vec<ElementRef> = match counter {
+        Some(sel) => element.select(sel).collect(),
+        None => vec![],
+    };
+    let skip: HashSet<_> = counters.iter().map(|c| c.id()).collect();
+    let text: String = element
+        .descendants()
+        .filter(|node| !node.ancestors().any(|a| skip.contains(&a.id())))
+        .filter_map(|node| node.value().as_text().map(|t| &**t))
+        .collect();
+    let count = counters
+        .first()
+        .and_then(|c| parse_count(&c.text().collect::<String>()));
+    (normalize_text(&text), count)
+}
+
+/// HTML 조각으로 저장된 제목(이전 버전의 `inner_html`)을 정리합니다.
+pub fn normalize_html(html: &str) -> (String, Option<u32>) {
+    let fragment = Html::parse_fragment(html);
+    let counter = Selector::parse(LEGACY_COUNTERS).ok();
+    normalize(fragment.root_element(), counter.as_ref())
+}
+
+/// 공백을 한 칸으로 줄입니다. 제목 끝의 `[2024]` 같은 글자는 그대로 둡니다.
+pub fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 따로 표시된 댓글 수(`"[12]"`, `"[12/3]"`)를 읽습니다. 슬래시 뒤(보이스 댓글 등)는 버립니다.
+pub fn parse_count(text: &str) -> Option<u32> {
+    let inner = text.trim().trim_start_matches('[').trim_end_matches(']');
+    inner.split('/').next()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title_of(html: &str, counter: Option<&str>) -> (String, Option<u32>) {
+        let fragment = Html::parse_fragment(html);
+        let a = fragment.select(&Selector::parse("a").unwrap()).next().unwrap();
+        let counter = counter.map(|css| Selector::parse(css).unwrap());
+        normalize(a, counter.as_ref())
+    }
+
+    #[test]
+    fn bracketed_number_stays_in_title() {
+        assert_eq!(normalize_text("  결산   [2024] "), "결산 [2024]");
+        assert_eq!(title_of(r#"<a href="/v">아이폰 [16]</a>"#, None), ("아이폰 [16]".to_string(), None));
+        // 디시는 댓글 수가 제목 링크 밖(`span.reply_num`)에 있습니다.
+        assert_eq!(
+            title_of(r#"<a href="/v"><em class="icon_pic"></em>결산 [2024]</a>"#, Some("span.reply_num")),
+            ("결산 [2024]".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn count_read_from_counter_element() {
+        let html = r#"<a href="/v">  Tom &amp; Jerry&nbsp;&lt;3
+            <span class="comment_count">[12]</span></a>"#;
+        assert_eq!(title_of(html, Some("span.comment_count")), ("Tom & Jerry <3".to_string(), Some(12)));
+
+        let html = r#"<a href="/v"><i class="icon_pic"></i> 아이폰 [16] <span class="replycnt">[3]</span></a>"#;
+        assert_eq!(title_of(html, Some("span.replycnt")), ("아이폰 [16]".to_string(), Some(3)));
+    }
+
+    #[test]
+    fn legacy_inner_html() {
+        assert_eq!(
+            normalize_html(r#"결산 [2024] <span class="replycnt">[7/1]</span>"#),
+            ("결산 [2024]".to_string(), Some(7))
+        );
+        assert_eq!(normalize_html("아이폰 [16]"), ("아이폰 [16]".to_string(), None));
+    }
+
+    #[test]
+    fn counts() {
+        assert_eq!(parse_count(" [12] "), Some(12));
+        assert_eq!(parse_count("[12/3]"), Some(12));
+        assert_eq!(parse_count("댓글"), None);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::warn;
+use chrono::Utc;
+use chrono_tz::Asia::Seoul;
+use crate::models::{List, Post, StoredPost};
+use crate::scrapers::{self, Scraper};
+use crate::utils;
+use super::{refresh_new_flag, Storage, MAX_POST_AGE_SECS};
+
+/// 최근 72시간의 게시물을 JSON 배열 하나로 저장합니다. 매 주기 파일 전체를 다시 씁니다.
+pub struct JsonStorage {
+    path: String,
+    scraper: Arc<dyn Scraper>,
+    backups: u32,
+}
+
+impl JsonStorage {
+    pub fn new(path: &str, scraper: Arc<dyn Scraper>, backups: u32) -> Self {
+        JsonStorage {
+            path: path.to_string(),
+            scraper,
+            backups,
+        }
+    }
+
+    /// 저장 파일의 항목들. 파일이 깨져 있으면 옮겨 두고 가장 최근의 성한 백업을 읽습니다.
+    async fn read_entries(&self) -> Result<Vec<StoredPost>> {
+        if !Path::new(&self.path).exists() {
+            return Ok(vec![]);
+        }
+        if let Some(v) = utils::read_json_file(&self.path).await? {
+            return Ok(v);
+        }
+        for n in 1..=self.backups {
+            let backup = format!("{}.{}", self.path, n);
+            if let Ok(Some(v)) = utils::read_json_file(&backup).await {
+                warn!("{}: restored from {}", self.path, backup);
+                return Ok(v);
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// 저장 파일을 읽습니다. 이전 형식(`List`)의 항목은 스크래퍼로 식별자를 붙여 `Post` 로 바꿉니다.
+    async fn load(&self) -> Result<Vec<Post>> {
+        let load_list = self.read_entries().await?;
+        let _stamp = Utc::now().with_timezone(&Seoul).timestamp();
+
+        let mut seen_ids = HashSet::new();
+        let posts = load_list
+            .into_iter()
+            .map(|x| match x {
+                StoredPost::Post(v) => v,
+                StoredPost::Legacy(v) => upgrade(self.scraper.as_ref(), v),
+            })
+            .filter_map(|mut x| {
+                refresh_new_flag(&mut x, _stamp);
+                if (_stamp - x.timestamp) < MAX_POST_AGE_SECS && seen_ids.insert(x.id.clone()) {
+                    Some(x)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Ok(posts)
+    }
+}
+
+#[async_trait]
+impl Storage for JsonStorage {
+    async fn known_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        Ok(self
+            .load()
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .filter(|id| ids.contains(id))
+            .collect())
+    }
+
+    async fn recent(&self, limit: usize) -> Result<Vec<Post>> {
+        let mut posts = self.load().await?;
+        posts.sort_by_key(|x| std::cmp::Reverse(x.timestamp));
+        posts.truncate(limit);
+        Ok(posts)
+    }
+
+    async fn merge(&self, posts: &[Post]) -> Result<Vec<Post>> {
+        // 읽기에 실패하면 덮어써서 기록을 잃지 않도록 저장하지 않습니다.
+        let _loadfile = self.load().await?;
+        let new_posts = newer_to_list(posts, &_loadfile);
+        let merged = merge_to_list(posts, &_loadfile);
+        let save_json = serde_json::to_value(merged)
+            .context(format!("Failed to serialize {}", self.path))?;
+        utils::rotate_backups(&self.path, self.backups).await?;
+        utils::file_save_from_json(&self.path, &save_json).await?;
+        Ok(new_posts)
+    }
+}
+
+/// 이전 형식 항목을 `Post` 로 바꿉니다. 링크와 id 는 `Scraper::parse_page` 와 같은 방법으로 정합니다.
+fn upgrade(scraper: &dyn Scraper, v: List) -> Post {
+    let mut post = Post::from(v);
+    post.link = scraper.canonical_link(&post.link);
+    scraper.identify(&mut post);
+    // 이전 버전은 fm / mp 제목을 `inner_html` 그대로 저장했습니다.
+    let (title, comment_count) = scrapers::title::normalize_html(&post.title);
+    post.title = title;
+    post.comment_count = comment_count;
+    post
+}
+
+fn newer_to_list(a: &[Post], b: &[Post]) -> Vec<Post> {
+    let existing_ids: HashSet<&str> = b.iter().map(|item| item.id.as_str()).collect();
+
+    a.iter()
+        .filter(|item| !existing_ids.contains(item.id.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn merge_to_list(a: &[Post], b: &[Post]) -> Vec<Post> {
+    let mut result = Vec::new();
+    let mut seen_ids = HashSet::new();
+
+    // 먼저 b 리스트의 항목을 추가하면서 id 를 추적합니다.
+    for item in b {
+        if seen_ids.insert(item.id.as_str()) {
+            result.push(item.clone());
+        }
+    }
+
+    for item in a {
+        if seen_ids.insert(item.id.as_str()) {
+            result.push(item.clone());
+        }
+    }
+
+    result.sort_by_key(|x| std::cmp::Reverse(x.timestamp));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Site;
+    use crate::posttime;
+
+    fn legacy(link: &str) -> List {
+        serde_json::from_value(serde_json::json!({
+            "timestamp": 1729213200,
+            "title": "제목",
+            "datetime": "12:00",
+            "link": link,
+            "images": "",
+            "more": "label",
+            "new": true
+        }))
+        .unwrap()
+    }
+
+    fn parsed_id(host: &str, url: &str, html: &str) -> String {
+        let site = Site {
+            host: host.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        };
+        let scraper = scrapers::for_site(&site).unwrap();
+        let posts = scraper.parse_page(html, &site, &[]).unwrap();
+        assert_eq!(posts.len(), 1);
+        posts[0].id.clone()
+    }
+
+    #[test]
+    fn legacy_dc_id_matches_parse_page() {
+        let now = posttime::now_in(posttime::DEFAULT_TIMEZONE).format("%Y-%m-%d %H:%M:%S");
+        let html = format!(
+            r#"<table><tr class="ub-content"><td class="gall_tit"><a href="/board/view/?id=baseball_new11&no=12345&page=1">제목</a></td><td class="gall_writer" data-nick="n"></td><td class="gall_date" title="{}">12:00</td></tr></table>"#,
+            now
+        );
+        let parsed = parsed_id("dc", "https://gall.dcinside.com/board/lists/?id=baseball_new11", &html);
+
+        let link = "https://gall.dcinside.com/board/view/?id=baseball_new11&no=12345&page=1&exception_mode=recommend";
+        let post = upgrade(scrapers::find("dc").unwrap().as_ref(), legacy(link));
+        assert_eq!(post.id, "dc:baseball_new11:12345");
+        assert_eq!(post.id, parsed);
+    }
+
+    #[test]
+    fn legacy_mp_id_matches_parse_page() {
+        let html = r#"<div class="lists_today_contxt"><ul><li class="items"><a href="/mp/b.php?p=1&m=view&b=bullpen&id=202410180012345&select=&query=">제목</a></li></ul></div>"#;
+        let parsed = parsed_id("mp_low", "https://mlbpark.donga.com/mp/", html);
+
+        let link = "https://mlbpark.donga.com/mp/b.php?p=31&m=view&b=bullpen&id=202410180012345&select=&query=&user=&site=donga.com";
+        let post = upgrade(scrapers::find("mp").unwrap().as_ref(), legacy(link));
+        assert_eq!(post.id, "mp:bullpen:202410180012345");
+        assert_eq!(post.id, parsed);
+    }
+}
@@ -0,0 +1,86 @@
+pub mod json;
+pub mod sqlite;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use anyhow::Result;
+use async_trait::async_trait;
+use log::warn;
+use crate::models::{Config, Post, Save};
+use crate::scrapers::{self, Scraper, SeenIds};
+
+// Timing constants (in seconds)
+pub const NEW_MARKER_AGE_SECS: i64 = 28800; // 8 hours - posts newer than this keep "new" flag
+pub const MAX_POST_AGE_SECS: i64 = 259200; // 72 hours - posts older than this are filtered out
+
+/// 수집한 게시물을 보관하는 곳
+///
+/// 같은 저장소를 여러 사이트가 함께 쓸 수 있으므로(mp / mp_low) 호출하는 쪽에서 `save_lock` 으로 묶습니다.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// `ids` 가운데 이미 보관 중인 게시물 id
+    async fn known_ids(&self, ids: &[String]) -> Result<HashSet<String>>;
+
+    /// 수집한 게시물을 합쳐 저장하고, 처음 보는 게시물을 돌려줍니다.
+    async fn merge(&self, posts: &[Post]) -> Result<Vec<Post>>;
+
+    /// 최신순(`timestamp`) 게시물 최대 `limit` 개
+    async fn recent(&self, limit: usize) -> Result<Vec<Post>>;
+}
+
+/// 저장 설정에 맞는 저장소를 엽니다. `scraper` 는 저장 host 의 스크래퍼로, 이전 형식 파일을 읽을 때 씁니다.
+pub fn open(save: &Save, scraper: Arc<dyn Scraper>) -> Box<dyn Storage> {
+    match &save.sqlite_path {
+        Some(path) => Box::new(sqlite::SqliteStorage::new(
+            path,
+            scraper.site_id(),
+            save.retention_days,
+        )),
+        None => Box::new(json::JsonStorage::new(&save.json_path, scraper, save.backups)),
+    }
+}
+
+/// 사이트 id 가 `site_id` 인 게시물을 받는 저장 설정과 그 저장소
+pub fn for_site_id<'a>(config: &'a Config, site_id: &str) -> Vec<(&'a Save, Box<dyn Storage>)> {
+    config
+        .saves
+        .iter()
+        .filter_map(|save| {
+            let scraper = scrapers::for_save(&save.host, &config.sites)?;
+            (scraper.site_id() == site_id).then(|| (save, open(save, scraper)))
+        })
+        .collect()
+}
+
+/// 사이트 id 가 같은 모든 저장소에서 이미 저장된 게시물을 찾습니다.
+pub struct SavedIds<'a> {
+    config: &'a Config,
+    site_id: &'a str,
+}
+
+impl<'a> SavedIds<'a> {
+    pub fn new(config: &'a Config, site_id: &'a str) -> Self {
+        SavedIds { config, site_id }
+    }
+}
+
+#[async_trait]
+impl SeenIds for SavedIds<'_> {
+    async fn known(&self, ids: &[String]) -> HashSet<String> {
+        let mut known = HashSet::new();
+        for (save, store) in for_site_id(self.config, self.site_id) {
+            match store.known_ids(ids).await {
+                Ok(v) => known.extend(v),
+                Err(e) => warn!("{}: {:#}", save.path(), e),
+            }
+        }
+        known
+    }
+}
+
+/// 게시물이 처음 수집된 지 8시간이 지났으면 `new` 표시를 끕니다.
+pub fn refresh_new_flag(post: &mut Post, now: i64) {
+    if now - post.first_seen_at > NEW_MARKER_AGE_SECS {
+        post.flags.new = false;
+    }
+}
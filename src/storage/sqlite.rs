@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use crate::models::Post;
+use super::{refresh_new_flag, Storage};
+
+// 다른 작업이 쓰는 중일 때 기다리는 최대 시간
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+PRAGMA journal_mode = WAL;
+CREATE TABLE IF NOT EXISTS posts (
+    id            TEXT PRIMARY KEY,
+    site          TEXT NOT NULL,
+    board         TEXT NOT NULL,
+    post_no       TEXT NOT NULL,
+    title         TEXT NOT NULL,
+    link          TEXT NOT NULL,
+    author        TEXT NOT NULL,
+    timestamp     INTEGER NOT NULL,
+    posted_at     INTEGER,
+    first_seen_at INTEGER NOT NULL,
+    last_seen_at  INTEGER NOT NULL,
+    comment_count INTEGER,
+    data          TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS posts_site_timestamp ON posts (site, timestamp);
+CREATE INDEX IF NOT EXISTS posts_site_first_seen ON posts (site, first_seen_at);
+";
+
+// 이미 있는 게시물은 처음 저장한 내용(처음 본 시각, 상세 정보)을 두고
+// 마지막으로 본 시각, 댓글 수, 뒤늦게 알게 된 작성 시각만 고칩니다.
+const UPSERT: &str = "
+INSERT INTO posts (id, site, board, post_no, title, link, author, timestamp, posted_at,
+                   first_seen_at, last_seen_at, comment_count, data)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+ON CONFLICT (id) DO UPDATE SET
+    last_seen_at  = excluded.last_seen_at,
+    comment_count = COALESCE(excluded.comment_count, posts.comment_count),
+    posted_at     = COALESCE(posts.posted_at, excluded.posted_at)
+";
+
+/// 게시물을 SQLite `posts` 테이블에 쌓습니다. `id` 로 구분하고 보관 기간이 지난 글만 지웁니다.
+pub struct SqliteStorage {
+    path: String,
+    site_id: String,
+    retention_days: Option<u32>,
+}
+
+fn connect(path: &str) -> Result<Connection> {
+    let conn = Connection::open(path).context(format!("Failed to open database: {}", path))?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.execute_batch(SCHEMA)
+        .context(format!("Failed to create tables in {}", path))?;
+    Ok(conn)
+}
+
+impl SqliteStorage {
+    pub fn new(path: &str, site_id: &str, retention_days: Option<u32>) -> Self {
+        SqliteStorage {
+            path: path.to_string(),
+            site_id: site_id.to_string(),
+            retention_days,
+        }
+    }
+
+    /// 연결을 열어 `f` 를 블로킹 스레드에서 실행합니다.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || f(&mut connect(&path)?))
+            .await
+            .context("database task panicked")?
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn known_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        let ids = ids.to_vec();
+        self.with_conn(move |conn| {
+            let mut exists = conn.prepare("SELECT 1 FROM posts WHERE id = ?1")?;
+            let mut known = HashSet::new();
+            for id in ids {
+                if exists.exists([&id])? {
+                    known.insert(id);
+                }
+            }
+            Ok(known)
+        })
+        .await
+    }
+
+    async fn recent(&self, limit: usize) -> Result<Vec<Post>> {
+        let site = self.site_id.clone();
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_conn(move |conn| {
+            let now = Utc::now().timestamp();
+            let mut stmt = conn.prepare(
+                "SELECT data, first_seen_at, comment_count, posted_at FROM posts
+                 WHERE site = ?1 ORDER BY timestamp DESC LIMIT ?2",
+            )?;
+            let rows = stmt
+                .query_map(params![site, limit], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, Option<u32>>(2)?,
+                        row.get::<_, Option<i64>>(3)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut posts = Vec::with_capacity(rows.len());
+            // 처음 저장한 `data` 이후에 고친 열은 열 쪽 값을 씁니다.
+            for (data, first_seen_at, comment_count, posted_at) in rows {
+                let mut post: Post = serde_json::from_str(&data).context("Failed to read stored post")?;
+                post.first_seen_at = first_seen_at;
+                post.comment_count = comment_count;
+                post.posted_at = posted_at;
+                refresh_new_flag(&mut post, now);
+                posts.push(post);
+            }
+            Ok(posts)
+        })
+        .await
+    }
+
+    async fn merge(&self, posts: &[Post]) -> Result<Vec<Post>> {
+        let posts = posts.to_vec();
+        let site = self.site_id.clone();
+        let retention = self.retention_days;
+        self.with_conn(move |conn| {
+            let now = Utc::now().timestamp();
+            let tx = conn.transaction()?;
+            let mut new_posts = vec![];
+            {
+                let mut exists = tx.prepare("SELECT 1 FROM posts WHERE id = ?1")?;
+                let mut upsert = tx.prepare(UPSERT)?;
+                for post in &posts {
+                    if !exists.exists([&post.id])? {
+                        new_posts.push(post.clone());
+                    }
+                    let data = serde_json::to_string(post).context("Failed to serialize post")?;
+                    upsert.execute(params![
+                        post.id,
+                        post.site,
+                        post.board,
+                        post.post_no,
+                        post.title,
+                        post.link,
+                        post.author,
+                        post.timestamp,
+                        post.posted_at,
+                        post.first_seen_at,
+                        now,
+                        post.comment_count,
+                        data,
+                    ])?;
+                }
+            }
+            if let Some(days) = retention {
+                let cutoff = now - i64::from(days) * 86400;
+                tx.execute(
+                    "DELETE FROM posts WHERE site = ?1 AND timestamp < ?2",
+                    params![site, cutoff],
+                )?;
+            }
+            tx.commit()?;
+            Ok(new_posts)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 테스트마다 따로 쓰는 임시 DB. 끝나면 지웁니다.
+    struct TempDb(String);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("textminer-{}-{}.db", std::process::id(), name));
+            let db = TempDb(path.to_string_lossy().into_owned());
+            db.remove();
+            db
+        }
+
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0, suffix));
+            }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    fn post(id: &str, timestamp: i64, first_seen_at: i64) -> Post {
+        Post {
+            id: id.to_string(),
+            site: "dc".to_string(),
+            title: format!("제목 {}", id),
+            link: format!("https://example.com/{}", id),
+            timestamp,
+            first_seen_at,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_keeps_first_seen_and_reports_new_once() {
+        let db = TempDb::new("merge");
+        let store = SqliteStorage::new(&db.0, "dc", None);
+        let now = Utc::now().timestamp();
+
+        let first = store.merge(&[post("dc:1", now, now - 60)]).await.unwrap();
+        assert_eq!(first.len(), 1);
+
+        let mut again = post("dc:1", now, now);
+        again.comment_count = Some(3);
+        let second = store.merge(&[again]).await.unwrap();
+        assert!(second.is_empty());
+
+        let stored = store.recent(10).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].first_seen_at, now - 60);
+        assert_eq!(stored[0].comment_count, Some(3));
+    }
+
+    #[tokio::test]
+    async fn retention_removes_old_rows() {
+        let db = TempDb::new("retention");
+        let store = SqliteStorage::new(&db.0, "dc", Some(1));
+        let now = Utc::now().timestamp();
+        let old = now - 2 * 86400;
+
+        store
+            .merge(&[post("dc:old", old, old), post("dc:new", now, now)])
+            .await
+            .unwrap();
+        let ids: Vec<String> = store.recent(10).await.unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, ["dc:new"]);
+    }
+
+    #[tokio::test]
+    async fn recent_is_newest_first() {
+        let db = TempDb::new("recent");
+        let store = SqliteStorage::new(&db.0, "dc", None);
+        let now = Utc::now().timestamp();
+
+        store
+            .merge(&[post("dc:2", now - 20, now), post("dc:3", now - 10, now), post("dc:1", now - 30, now)])
+            .await
+            .unwrap();
+        let ids: Vec<String> = store.recent(2).await.unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, ["dc:3", "dc:2"]);
+    }
+
+    #[tokio::test]
+    async fn known_ids_only_reports_stored() {
+        let db = TempDb::new("known");
+        let store = SqliteStorage::new(&db.0, "dc", None);
+        let now = Utc::now().timestamp();
+
+        store.merge(&[post("dc:1", now, now)]).await.unwrap();
+        let known = store
+            .known_ids(&["dc:1".to_string(), "dc:2".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(known, HashSet::from(["dc:1".to_string()]));
+    }
+}
@@ -1,39 +1,62 @@
-use serde_json::{json, Value};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
 use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use bytes::Bytes;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, REFERER, RETRY_AFTER};
 use lazy_static::lazy_static;
 use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
 use anyhow::{Result, Context};
 // ⭐ log 매크로 사용 (flexi_logger가 이 로그들을 받아 처리합니다)
 use log::{info, warn, error};
+use flexi_logger::{Age, Cleanup, Criterion, Duplicate, FileSpec, Logger, LoggerHandle, Naming};
+use crate::{cache, charset, clients};
+use crate::models::RetryPolicy;
+use crate::ratelimit::{self, Permit};
 
-// HTTP client configuration constants
-const HTTP_TIMEOUT_SECS: u64 = 30;
-const POOL_MAX_IDLE_PER_HOST: usize = 10;
-const APP_USER_AGENT: &str = "RS Simple Scraper/1.0";
-const BOT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:98.0) Gecko/20100101 Firefox/98.0";
+// Logging constants
+const LOG_DIR: &str = "./log";
+const LOG_LEVEL: &str = "info";
+const LOG_KEEP_FILES: usize = 31;
+
+// Retry-After 가 이보다 길면 재시도하지 않고 포기합니다.
+const MAX_RETRY_AFTER_SECS: u64 = 300;
+
+/// 조건부 요청에 쓰는 응답 검증자
+#[derive(Debug, Clone, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
 
 lazy_static! {
-    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(HTTP_TIMEOUT_SECS))
-        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
-        .user_agent(APP_USER_AGENT)
-        .build()
-        .expect("Failed to create HTTP client");
-
-    static ref HTTP_CLIENT_BOT: reqwest::Client = {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(reqwest::header::AUTHORIZATION, reqwest::header::HeaderValue::from_static("secret"));
-        reqwest::Client::builder()
-            .user_agent(BOT_USER_AGENT)
-            .default_headers(headers)
-            .timeout(Duration::from_secs(HTTP_TIMEOUT_SECS))
-            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
-            .build()
-            .expect("Failed to create bot HTTP client")
-    };
+    // URL 별로 마지막으로 저장까지 끝낸 응답의 검증자
+    static ref VALIDATORS: Mutex<HashMap<String, Validators>> = Mutex::new(HashMap::new());
+}
+
+// 동시에 쓰는 임시 파일끼리 이름이 겹치지 않게 붙이는 번호
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// `./log` 디렉터리에 하루 단위로 나누어 로그를 남기고, 경고 이상은 stderr 에도 출력합니다.
+/// 돌려받은 핸들이 살아 있는 동안 로그가 기록됩니다.
+pub fn init_logger() -> Result<LoggerHandle> {
+    Logger::try_with_env_or_str(LOG_LEVEL)
+        .context("Invalid log level")?
+        .log_to_file(FileSpec::default().directory(LOG_DIR))
+        .format_for_files(flexi_logger::detailed_format)
+        .rotate(Criterion::Age(Age::Day), Naming::Timestamps, Cleanup::KeepLogFiles(LOG_KEEP_FILES))
+        .append()
+        .duplicate_to_stderr(Duplicate::Warn)
+        .start()
+        .context("Failed to start logger")
 }
 
 /// ⭐ 기존의 복잡한 수동 로깅 함수를 대체합니다.
@@ -51,85 +74,337 @@ pub async fn make_file(path: &str, file_name: &str, bin: &Bytes) -> Result<()> {
         fs::create_dir_all(path).await.context(format!("Failed to create directory: {}", path))?;
     }
     let file_path = format!("{}/{}", path, file_name);
-    let mut file = File::create(&file_path).await.context(format!("Failed to create file: {}", file_path))?;
-    file.write_all(bin).await.context(format!("Failed to write to file: {}", file_path))?;
+    write_atomic(Path::new(&file_path), bin)
+        .await
+        .context(format!("Failed to write to file: {}", file_path))
+}
+
+/// 같은 폴더의 임시 파일에 쓰고 디스크에 내린(fsync) 뒤 이름을 바꿔 덮어씁니다.
+///
+/// 쓰는 도중에 멈추거나 디스크가 가득 차도 원래 파일은 그대로 남습니다.
+pub async fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("file");
+    let tmp = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = async {
+        let mut file = File::create(&tmp).await?;
+        file.write_all(data).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp).await;
+        return result;
+    }
+    // 이름 바꾸기까지 디스크에 남도록 폴더도 내립니다. 지원하지 않는 환경이면 넘어갑니다.
+    let dir = match path.parent() {
+        Some(v) if !v.as_os_str().is_empty() => v,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = File::open(dir).await {
+        let _ = dir.sync_all().await;
+    }
     Ok(())
 }
 
-pub fn json_result(_res: &str) -> Value {
-    json!({ "result": _res })
+/// 덮어쓰기 전에 지금 파일을 `{path}.1` 로 복사하고, 이전 백업은 `.2` ~ `.{keep}` 로 한 칸씩 밉니다.
+pub async fn rotate_backups(path: &str, keep: u32) -> Result<()> {
+    if keep == 0 || !path_exist(path) {
+        return Ok(());
+    }
+    for n in (1..keep).rev() {
+        let from = format!("{}.{}", path, n);
+        if path_exist(&from) {
+            fs::rename(&from, format!("{}.{}", path, n + 1))
+                .await
+                .context(format!("Failed to rotate backup: {}", from))?;
+        }
+    }
+    let data = fs::read(path).await.context(format!("Failed to read file: {}", path))?;
+    write_atomic(Path::new(&format!("{}.1", path)), &data)
+        .await
+        .context(format!("Failed to write backup of {}", path))
+}
+
+/// 읽을 수 없는 파일을 `{path}.corrupt-{시각}` 으로 옮겨 두고 그 경로를 돌려줍니다.
+pub async fn quarantine(path: &str) -> Result<String> {
+    let target = format!("{}.corrupt-{}", path, chrono::Utc::now().format("%Y%m%d%H%M%S"));
+    fs::rename(path, &target)
+        .await
+        .context(format!("Failed to move corrupt file: {}", path))?;
+    Ok(target)
 }
 
-pub async fn file_read_to_json(_filepath: &str) -> Result<Value> {
-    match fs::read_to_string(_filepath).await {
-        Ok(content) => {
-            serde_json::from_str(&content).context("Failed to parse JSON")
+/// JSON 파일을 `T` 로 읽습니다. 파일이 없으면 `None` 입니다.
+///
+/// 내용이 깨져 있으면 지우지 않고 `quarantine` 으로 옮긴 뒤 `None` 을 돌려줍니다.
+/// 읽기 자체가 실패하면(권한 등) 덮어쓰지 않도록 오류를 돌려줍니다.
+pub async fn read_json_file<T: DeserializeOwned>(path: &str) -> Result<Option<T>> {
+    let content = match fs::read_to_string(path).await {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            error!("Error reading {}: {}", path, e);
+            return Err(e).context(format!("Failed to read file: {}", path));
         }
+    };
+    match serde_json::from_str(&content) {
+        Ok(v) => Ok(Some(v)),
         Err(e) => {
-            // ⭐ 수동 logger() 대신 log 매크로 사용
-            error!("Error reading {}: {}", _filepath, e);
-            Ok(json_result(&e.to_string()))
+            let moved = quarantine(path).await?;
+            error!("Corrupt JSON in {} ({}), moved to {}", path, e, moved);
+            Ok(None)
         }
     }
 }
 
 pub async fn file_save_from_json(_filepath: &str, _v: &Value) -> Result<()> {
     let json = serde_json::to_string(_v).context("Failed to serialize JSON")?;
-    let mut file = File::create(_filepath).await.context(format!("Failed to create file: {}", _filepath))?;
-    file.write_all(json.as_bytes()).await.context(format!("Failed to write to file: {}", _filepath))?;
-    Ok(())
+    write_atomic(Path::new(_filepath), json.as_bytes())
+        .await
+        .context(format!("Failed to write to file: {}", _filepath))
 }
 
-pub async fn get_text_response(_url: &str) -> String {
-    match HTTP_CLIENT.get(_url).send().await {
-        Ok(resp) => {
-            match resp.text().await {
-                Ok(result) => result,
-                Err(e) => {
-                    warn!("Failed to get text from {}: {}", _url, e);
-                    String::new()
-                }
+/// HTTP 요청 실패 원인
+#[derive(Debug)]
+pub enum FetchError {
+    /// 연결 실패, 타임아웃, 본문 읽기 실패 등
+    Request { url: String, source: reqwest::Error },
+    /// 성공(2xx)이 아닌 응답
+    Status { url: String, status: StatusCode },
+}
+
+impl FetchError {
+    /// 다시 시도하면 성공할 수 있는 오류인지 확인합니다.
+    fn is_retryable(&self) -> bool {
+        match self {
+            FetchError::Request { source, .. } => !source.is_builder(),
+            FetchError::Status { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
             }
-        },
-        Err(e) => {
-            warn!("Failed to request {}: {}", _url, e);
-            String::new()
         }
     }
 }
 
-pub async fn get_byte_response(_url: &str, reffer: &str) -> Bytes {
-    match HTTP_CLIENT.get(_url).header("Referer", reffer).send().await {
-        Ok(resp) => {
-            match resp.bytes().await {
-                Ok(bin) => bin,
-                Err(e) => {
-                    warn!("Failed to get bytes from {}: {}", _url, e);
-                    Bytes::new()
-                }
-            }
-        },
-        Err(e) => {
-            warn!("Failed to request bytes from {}: {}", _url, e);
-            Bytes::new()
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Request { url, source } => write!(f, "request to {} failed: {}", url, source),
+            FetchError::Status { url, status } => write!(f, "{} returned {}", url, status),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FetchError::Request { source, .. } => Some(source),
+            FetchError::Status { .. } => None,
         }
     }
 }
 
-pub async fn get_text_response_bot(_url: &str) -> String {
-    match HTTP_CLIENT_BOT.get(_url).send().await {
-        Ok(resp) => {
-            match resp.text().await {
-                Ok(result) => result,
-                Err(e) => {
-                    warn!("Failed to get bot text from {}: {}", _url, e);
-                    String::new()
+/// 0 ~ max 사이의 무작위 시간. 별도 난수 크레이트 없이 RandomState 의 시드를 사용합니다.
+pub fn random_upto(max: Duration) -> Duration {
+    let millis = max.as_millis() as u64;
+    if millis == 0 {
+        return Duration::ZERO;
+    }
+    let seed = RandomState::new().build_hasher().finish();
+    Duration::from_millis(seed % (millis + 1))
+}
+
+/// n 번째 재시도 전 대기 시간. 지수적으로 늘어나며 절반은 무작위 지터입니다.
+fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let exp = policy
+        .base_delay_ms
+        .saturating_mul(1u64 << attempt.min(20))
+        .min(policy.max_delay_ms);
+    Duration::from_millis(exp / 2) + random_upto(Duration::from_millis(exp - exp / 2))
+}
+
+/// 429 / 503 응답의 Retry-After 헤더 (초 또는 HTTP 날짜)
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    if resp.status() != StatusCode::TOO_MANY_REQUESTS
+        && resp.status() != StatusCode::SERVICE_UNAVAILABLE
+    {
+        return None;
+    }
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+/// 프로필의 재시도 설정에 따라 요청을 보내고, 2xx 와 304 응답만 돌려줍니다.
+///
+/// 매 시도마다 호스트별 속도 제한을 거치며, 돌려받은 허가는 본문을 다 읽을 때까지 들고 있어야 합니다.
+async fn send_with_retry<F>(url: &str, profile: &str, build: F) -> Result<(reqwest::Response, Permit), FetchError>
+where
+    F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+{
+    let http = clients::get(profile);
+    let mut attempt = 0;
+    loop {
+        let permit = ratelimit::acquire(url).await;
+        let (err, wait) = match build(&http.client).send().await {
+            Ok(resp) if resp.status().is_success() || resp.status() == StatusCode::NOT_MODIFIED => {
+                if attempt > 0 {
+                    info!("{} succeeded after {} retries", url, attempt);
                 }
+                return Ok((resp, permit));
             }
-        },
-        Err(e) => {
-            warn!("Failed to request bot text from {}: {}", _url, e);
-            String::new()
+            Ok(resp) => {
+                let wait = retry_after(&resp);
+                let err = FetchError::Status { url: url.to_string(), status: resp.status() };
+                (err, wait)
+            }
+            Err(e) => (FetchError::Request { url: url.to_string(), source: e }, None),
+        };
+        drop(permit);
+
+        if !err.is_retryable() || attempt >= http.retry.max_retries {
+            if attempt > 0 {
+                warn!("{} failed after {} retries: {}", url, attempt, err);
+            }
+            return Err(err);
+        }
+        let wait = match wait {
+            Some(v) if v > Duration::from_secs(MAX_RETRY_AFTER_SECS) => {
+                warn!("{}: Retry-After {:?} is too long, giving up", url, v);
+                return Err(err);
+            }
+            Some(v) => v,
+            None => backoff(&http.retry, attempt),
+        };
+        attempt += 1;
+        warn!(
+            "retry {}/{} for {} in {:?}: {}",
+            attempt, http.retry.max_retries, url, wait, err
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// 응답 본문을 읽어 문자열로 바꿉니다. 인코딩은 `charset::decode` 가 고릅니다.
+async fn read_text(resp: reqwest::Response, url: &str, encoding: Option<&str>) -> Result<String, FetchError> {
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let bytes = resp.bytes().await.map_err(|e| FetchError::Request { url: url.to_string(), source: e })?;
+    Ok(charset::decode(&bytes, content_type.as_deref(), encoding))
+}
+
+/// `encoding` 은 사이트 설정의 인코딩 이름이며, 없으면 헤더/BOM/meta 로 판단합니다.
+pub async fn get_text_response(_url: &str, profile: &str, encoding: Option<&str>) -> Result<String, FetchError> {
+    if let Some(body) = cache::read(_url).await {
+        return Ok(body);
+    }
+    let (resp, _permit) = send_with_retry(_url, profile, |c| c.get(_url)).await?;
+    let body = read_text(resp, _url, encoding).await?;
+    cache::write(_url, &body).await;
+    Ok(body)
+}
+
+/// 폼을 POST 로 보내고 응답 본문을 돌려줍니다. AJAX 엔드포인트용으로 `X-Requested-With` 를 붙입니다.
+pub async fn post_form_text(
+    _url: &str,
+    form: &[(&str, &str)],
+    reffer: &str,
+    profile: &str,
+) -> Result<String, FetchError> {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(form)
+        .finish();
+    let (resp, _permit) = send_with_retry(_url, profile, |c| {
+        c.post(_url)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded; charset=UTF-8")
+            .header(REFERER, reffer)
+            .header("X-Requested-With", "XMLHttpRequest")
+            .body(body.clone())
+    })
+    .await?;
+    read_text(resp, _url, None).await
+}
+
+/// 조건부 요청으로 받은 페이지. 게시물을 모두 저장한 뒤 `remember` 로 검증자를 저장합니다.
+pub struct Page {
+    pub body: String,
+    url: String,
+    validators: Validators,
+}
+
+impl Page {
+    /// 다음 요청에서 `If-None-Match` / `If-Modified-Since` 로 보낼 검증자를 저장합니다.
+    pub fn remember(self) {
+        let mut known = VALIDATORS.lock().unwrap_or_else(|e| e.into_inner());
+        if self.validators.etag.is_none() && self.validators.last_modified.is_none() {
+            known.remove(&self.url);
+        } else {
+            known.insert(self.url, self.validators);
+        }
+    }
+}
+
+/// `url` 에 저장된 검증자를 지웁니다. 다음 요청은 조건 없이 전체 페이지를 받습니다.
+pub fn forget_validators(url: &str) {
+    VALIDATORS.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+}
+
+/// 지난번 응답의 검증자를 보내고, 바뀌지 않았으면(304) `None` 을 돌려줍니다.
+pub async fn get_text_if_modified(
+    _url: &str,
+    profile: &str,
+    encoding: Option<&str>,
+) -> Result<Option<Page>, FetchError> {
+    if let Some(body) = cache::read(_url).await {
+        return Ok(Some(Page { body, url: _url.to_string(), validators: Validators::default() }));
+    }
+    let known = VALIDATORS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(_url)
+        .cloned()
+        .unwrap_or_default();
+    let (resp, _permit) = send_with_retry(_url, profile, |c| {
+        let mut req = c.get(_url);
+        if let Some(v) = &known.etag {
+            req = req.header(IF_NONE_MATCH, v);
         }
+        if let Some(v) = &known.last_modified {
+            req = req.header(IF_MODIFIED_SINCE, v);
+        }
+        req
+    })
+    .await?;
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
     }
-}
\ No newline at end of file
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let validators = Validators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+    let body = read_text(resp, _url, encoding).await?;
+    cache::write(_url, &body).await;
+    Ok(Some(Page { body, url: _url.to_string(), validators }))
+}
+
+pub async fn get_byte_response(_url: &str, reffer: &str, profile: &str) -> Result<Bytes, FetchError> {
+    let (resp, _permit) = send_with_retry(_url, profile, |c| c.get(_url).header(REFERER, reffer)).await?;
+    resp.bytes().await.map_err(|e| FetchError::Request { url: _url.to_string(), source: e })
+}